@@ -0,0 +1,54 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::hint::black_box;
+
+// GenPass is a binary crate, so include the bulk path straight from the source tree
+#[path = "../src/bulk.rs"]
+mod bulk;
+
+const COUNT: usize = 10_000;
+
+fn pools() -> [Vec<char>; 4] {
+    [
+        ('a'..='z').collect(),
+        ('A'..='Z').collect(),
+        "!#$%&()*+".chars().collect(),
+        ('0'..='9').collect(),
+    ]
+}
+
+fn bench_generate_many(c: &mut Criterion) {
+    let pools = pools();
+    let pools = [&pools[0][..], &pools[1][..], &pools[2][..], &pools[3][..]];
+    let counts = [8, 2, 2, 4];
+    let mut group = c.benchmark_group("generate 10k");
+
+    group.bench_function("generate_many", |b| {
+        let mut rng = StdRng::seed_from_u64(1);
+        b.iter(|| bulk::generate_many(black_box(counts), pools, COUNT, &mut rng, |_| {}));
+    });
+    // what --count did before: a fresh buffer per password
+    group.bench_function("one at a time", |b| {
+        let mut rng = StdRng::seed_from_u64(1);
+        b.iter(|| {
+            (0..COUNT)
+                .map(|_| {
+                    let mut buffer = Vec::new();
+                    bulk::fill_from_pools(
+                        &mut buffer,
+                        black_box(counts),
+                        pools,
+                        &mut Vec::new(),
+                        &mut rng,
+                    );
+                    buffer.into_iter().collect::<String>()
+                })
+                .collect::<Vec<_>>()
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_generate_many);
+criterion_main!(benches);
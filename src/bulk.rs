@@ -0,0 +1,63 @@
+// Bulk generation, free of the rest of the app so benches/generate_many.rs can
+// include this file directly.
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngExt};
+
+/// Fills `buffer` with `counts[i]` characters from `pools[i]`, then shuffles it.
+pub(crate) fn fill_from_pools(
+    buffer: &mut Vec<char>,
+    counts: [i32; 4],
+    pools: [&[char]; 4],
+    words: &mut Vec<u32>,
+    rng: &mut (impl Rng + ?Sized),
+) {
+    buffer.clear();
+    for (count, pool) in counts.into_iter().zip(pools) {
+        if pool.is_empty() || count <= 0 {
+            continue;
+        }
+        // one fill per class instead of one rng call per character
+        words.clear();
+        words.resize(count as usize, 0);
+        rng.fill(&mut words[..]);
+        for &word in words.iter() {
+            buffer.push(pool[pick(word, pool.len(), rng)]);
+        }
+    }
+    buffer.shuffle(rng);
+}
+
+/// Maps a random word onto `0..len` without modulo bias (Lemire's method),
+/// drawing a fresh word only for the rare rejected values.
+fn pick(mut word: u32, len: usize, rng: &mut (impl Rng + ?Sized)) -> usize {
+    let len = len as u32;
+    let threshold = len.wrapping_neg() % len;
+    loop {
+        let wide = u64::from(word) * u64::from(len);
+        if wide as u32 >= threshold {
+            return (wide >> 32) as usize;
+        }
+        word = rng.next_u32();
+    }
+}
+
+/// Generates `count` passwords through one reused buffer, calling `progress`
+/// with the number done after each one.
+pub(crate) fn generate_many(
+    counts: [i32; 4],
+    pools: [&[char]; 4],
+    count: usize,
+    rng: &mut (impl Rng + ?Sized),
+    mut progress: impl FnMut(usize),
+) -> Vec<String> {
+    let mut buffer = Vec::with_capacity(counts.iter().map(|&n| n.max(0) as usize).sum());
+    let mut words = Vec::new();
+    (1..=count)
+        .map(|done| {
+            fill_from_pools(&mut buffer, counts, pools, &mut words, rng);
+            progress(done);
+            buffer.iter().collect()
+        })
+        .collect()
+}
@@ -1,454 +1,6747 @@
 #[cfg(windows)]
-use arboard::Clipboard;
+use arboard::{Clipboard, SetExtWindows};
+use crossterm::cursor::Show;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
+use fancy_regex::Regex;
+use indicatif::{ProgressBar, ProgressFinish};
+use pbkdf2::pbkdf2_hmac;
+use qrcode::{Color as QrColor, QrCode};
 use rand::prelude::*;
+use rand::rand_core::UnwrapErr;
 use rand::rng;
+use rand::rngs::SysRng;
 use rand::seq::SliceRandom;
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Wrap};
+use ratatui::widgets::{
+    Block, Borders, Cell, Clear, Gauge, Paragraph, Row, Table, TableState, Wrap,
+};
 use ratatui::{Frame, Terminal};
-use std::io::{self, Stdout};
-#[cfg(unix)]
-use std::io::Write;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Stdout, Write};
+use std::path::{Path, PathBuf};
 #[cfg(unix)]
 use std::process::{Command, Stdio};
 use std::time::{Duration, Instant};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+mod bulk;
 
 const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
 const NUMBERS: &[u8] = b"0123456789";
 const SYMBOLS: &[u8] = b"!#$%&()*+";
+const AMBIGUOUS: &[char] = &['I', 'l', '1', 'O', '0', 'o'];
+const CONFUSABLE_GROUPS: [&str; 5] = ["0Oo", "1lI|", "5S", "2Z", "8B"];
+const SHELL_UNSAFE: &[char] = &[
+    '!', '$', '&', '(', ')', '*', '`', '\'', '"', '\\', ';', '|', '<', '>',
+];
+const CONSONANTS: &[u8] = b"bcdfghjklmnprstvz";
+const VOWELS: &[u8] = b"aeiou";
 const DEFAULT_LETTERS: i32 = 6;
 const DEFAULT_UPPERCASE: i32 = 2;
 const DEFAULT_SYMBOLS: i32 = 2;
 const DEFAULT_NUMBERS: i32 = 4;
 const MIN_VALUE: i32 = 0;
-const MAX_VALUE: i32 = 64;
+const STRENGTH_MIN_LENGTH: usize = 10;
+// 2 is bad arguments and 3 a clipboard failure
+const EXIT_UNMET: i32 = 4;
+const DERIVE_ROUNDS: u32 = 600_000;
+// bump whenever derived output would change, since users rely on it staying put
+const DERIVE_VERSION: u32 = 1;
+const PIN_MIN_DIGITS: i32 = 4;
+const PIN_MAX_DIGITS: i32 = 12;
+const PIN_GROUP_SIZE: usize = 4;
+const WORDLIST: &str = include_str!("wordlist.txt");
+// top 1000 of the Xato 10M password set, in the ranking the zxcvbn crate
+// (MIT, frequency_lists.rs) ships
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+const PASSPHRASE_SEPARATOR: char = '-';
+const DEFAULT_PASSPHRASE_WORDS: i32 = 7;
+const PASSPHRASE_MIN_WORDS: i32 = 3;
+const PASSPHRASE_MAX_WORDS: i32 = 12;
+const MIN_WORDLIST_WORDS: usize = 512;
+const COMBO_WORDS: i32 = 2;
+const DEFAULT_COMBO_DIGITS: i32 = 2;
+const COMBO_MIN_DIGITS: i32 = 1;
+const COMBO_MAX_DIGITS: i32 = 6;
+const GROUP_SEPARATOR: char = '-';
+const GROUP_SIZES: [usize; 3] = [3, 4, 5];
+const BATCH_SIZE: usize = 9;
+const MAX_BATCH_SIZE: usize = 20;
+const HISTORY_CAP: usize = 20;
+const HISTORY_VISIBLE: usize = 8;
+const MAX_GENERATION_ATTEMPTS: usize = 100;
+const MAX_AVOID_OVERLAP: f64 = 0.25;
+const MAX_REPAIR_ROUNDS: usize = 10;
+const DEFAULT_MAX_VALUE: i32 = 64;
+const MAX_TOTAL_LENGTH: i32 = 4096;
 const CLIPBOARD_MESSAGE_DURATION: Duration = Duration::from_secs(2);
+const CLIPBOARD_TIMEOUT: Duration = Duration::from_secs(1);
+const PROGRESS_STEP: usize = 500;
+const FLASH_DURATION: Duration = Duration::from_millis(300);
+const TYPE_COUNTDOWN_SECS: u32 = 3;
+const KEY_LOG_CAP: usize = 10;
+const HOLD_REPEAT_WINDOW: Duration = Duration::from_millis(150);
+const LENGTH_RATIOS: [i32; 4] = [50, 15, 15, 20];
 const FOCUS_FIELDS: usize = 4;
 const FOCUS_GENERATE: usize = 4;
 const FOCUS_COPY: usize = 5;
 const FOCUS_QUIT: usize = 6;
+const OUTPUT_VISIBLE_LINES: u16 = 3;
+// the full layout needs about this many rows before it starts clipping
+const COMPACT_HEIGHT: u16 = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Strength {
+    DoNotUse,
+    Weak,
+    Moderate,
+    Strong,
+}
+
+impl Strength {
+    fn label(self) -> &'static str {
+        match self {
+            Strength::DoNotUse => "Do not use!!!!",
+            Strength::Weak => "Weak",
+            Strength::Moderate => "Moderate",
+            Strength::Strong => "Strong",
+        }
+    }
 
-fn check_password_strength(password: &str) -> &'static str {
-    let length_criteria = password.len() >= 10;
-    let uppercase_criteria = password.chars().any(|ch| ch.is_ascii_uppercase());
-    let lowercase_criteria = password.chars().any(|ch| ch.is_ascii_lowercase());
-    let number_criteria = password.chars().any(|ch| ch.is_ascii_digit());
-    let symbol_criteria = password.chars().any(|ch| SYMBOLS.contains(&(ch as u8)));
-
-    let criteria_met = [
-        length_criteria,
-        uppercase_criteria,
-        lowercase_criteria,
-        number_criteria,
-        symbol_criteria,
+    fn parse(value: &str) -> Option<Strength> {
+        match value.to_ascii_lowercase().as_str() {
+            "weak" => Some(Strength::Weak),
+            "moderate" => Some(Strength::Moderate),
+            "strong" => Some(Strength::Strong),
+            _ => None,
+        }
+    }
+}
+
+fn strength_criteria(password: &str, min_len: usize) -> [(String, bool); 5] {
+    [
+        (
+            format!("Length ({min_len}+)"),
+            password.chars().count() >= min_len,
+        ),
+        (
+            "Uppercase".to_string(),
+            password.chars().any(|ch| ch.is_ascii_uppercase()),
+        ),
+        (
+            "Lowercase".to_string(),
+            password.chars().any(|ch| ch.is_ascii_lowercase()),
+        ),
+        (
+            "Number".to_string(),
+            password.chars().any(|ch| ch.is_ascii_digit()),
+        ),
+        (
+            "Symbol".to_string(),
+            password
+                .chars()
+                .any(|ch| ch == ' ' || (!ch.is_alphanumeric() && !ch.is_whitespace())),
+        ),
     ]
-    .iter()
-    .filter(|&&c| c)
-    .count();
+}
+
+fn check_password_strength(password: &str, min_len: usize) -> Strength {
+    let criteria_met = strength_criteria(password, min_len)
+        .iter()
+        .filter(|(_, met)| *met)
+        .count();
 
     if criteria_met == 5 {
-        "Strong"
+        Strength::Strong
     } else if criteria_met >= 4 {
-        "Moderate"
+        Strength::Moderate
     } else if criteria_met >= 3 {
-        "Weak"
+        Strength::Weak
     } else {
-        "Do not use!!!!"
+        Strength::DoNotUse
     }
 }
 
-fn generate_password(
-    letters: i32,
-    uppercase: i32,
-    symbols: i32,
-    numbers: i32,
-    rng: &mut impl Rng,
-) -> String {
-    let mut generated: Vec<u8> = Vec::new();
-
-    for _ in 0..letters {
-        generated.push(*LETTERS.choose(rng).unwrap());
+fn password_entropy(password: &str) -> f64 {
+    let has = |pred: fn(char) -> bool| password.chars().any(pred);
+    let mut pool = 0;
+    if has(|ch| ch.is_ascii_lowercase()) {
+        pool += LETTERS.len();
     }
-    for _ in 0..uppercase {
-        let letter = *LETTERS.choose(rng).unwrap();
-        generated.push(letter.to_ascii_uppercase());
+    if has(|ch| ch.is_ascii_uppercase()) {
+        pool += LETTERS.len();
     }
-    for _ in 0..symbols {
-        generated.push(*SYMBOLS.choose(rng).unwrap());
+    if has(|ch| ch.is_ascii_digit()) {
+        pool += NUMBERS.len();
     }
-    for _ in 0..numbers {
-        generated.push(*NUMBERS.choose(rng).unwrap());
+    if has(|ch| !ch.is_ascii_alphanumeric()) {
+        pool += SYMBOLS.len();
+    }
+    if pool <= 1 {
+        return 0.0;
     }
+    password.chars().count() as f64 * (pool as f64).log2()
+}
 
-    generated.shuffle(rng);
-    String::from_utf8(generated).unwrap_or_default()
+fn common_passwords() -> HashSet<&'static str> {
+    COMMON_PASSWORDS.lines().map(str::trim).collect()
 }
 
-struct App {
-    letters: i32,
-    uppercase: i32,
-    symbols: i32,
-    numbers: i32,
-    focus: usize,
-    password: String,
-    strength: String,
-    status: String,
-    status_until: Option<Instant>,
+fn is_common_password(password: &str, common: &HashSet<&str>) -> bool {
+    common.contains(password) || common.contains(password.to_lowercase().as_str())
 }
 
-impl App {
-    fn new() -> Self {
-        let mut app = Self {
-            letters: DEFAULT_LETTERS,
-            uppercase: DEFAULT_UPPERCASE,
-            symbols: DEFAULT_SYMBOLS,
-            numbers: DEFAULT_NUMBERS,
-            focus: 0,
-            password: String::new(),
-            strength: "".to_string(),
-            status: "".to_string(),
-            status_until: None,
-        };
+fn format_check_report(password: &str, common: Option<&HashSet<&str>>, min_len: usize) -> String {
+    let is_common = common.is_some_and(|common| is_common_password(password, common));
+    let strength = if is_common {
+        Strength::DoNotUse
+    } else {
+        check_password_strength(password, min_len)
+    };
+    let mut report = format!(
+        "Strength: {}\nEntropy: {:.1} bits\n",
+        strength.label(),
+        password_entropy(password)
+    );
+    if is_common {
+        report.push_str("Common password: yes\n");
+    }
+    for (name, met) in strength_criteria(password, min_len) {
+        report.push_str(&format!("{name}: {}\n", if met { "yes" } else { "no" }));
+    }
+    report
+}
+
+fn run_check(input: &mut impl io::Read, common_check: bool, min_len: usize) -> i32 {
+    let mut buffer = String::new();
+    if let Err(err) = input.read_to_string(&mut buffer) {
+        eprintln!("genpass: could not read stdin: {err}");
+        return 2;
+    }
+    let password = buffer.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        eprintln!("genpass: --check expects a password on stdin");
+        return 2;
+    }
+    let common = common_check.then(common_passwords);
+    print!(
+        "{}",
+        format_check_report(password, common.as_ref(), min_len)
+    );
+    0
+}
+
+fn run_simple(config: &Config, input: &mut impl io::BufRead, output: &mut impl Write) -> i32 {
+    let mut app = App::new(config);
+    eprintln!("[Enter] new  [c] copy  [q] quit");
+    let mut line = String::new();
+    loop {
+        if writeln!(output, "{}", app.display_password()).is_err() {
+            return 2;
+        }
+        loop {
+            line.clear();
+            match input.read_line(&mut line) {
+                Ok(0) => return 0,
+                Ok(_) => {}
+                Err(err) => {
+                    eprintln!("genpass: could not read stdin: {err}");
+                    return 2;
+                }
+            }
+            match line.trim() {
+                "" => break,
+                "q" => return 0,
+                "c" => {
+                    app.copy_password();
+                    let _ = writeln!(output, "{}", app.status);
+                }
+                other => eprintln!("genpass: unknown command {other:?}"),
+            }
+        }
         app.generate_password();
-        app
     }
+}
 
-    fn generate_password(&mut self) {
-        let mut rng = rng();
-        self.password = generate_password(
-            self.letters,
-            self.uppercase,
-            self.symbols,
-            self.numbers,
-            &mut rng,
-        );
-        self.strength = check_password_strength(&self.password).to_string();
+fn active_confusables(enabled: &[bool]) -> Vec<&'static str> {
+    CONFUSABLE_GROUPS
+        .iter()
+        .zip(enabled)
+        .filter_map(|(group, &on)| on.then_some(*group))
+        .collect()
+}
+
+fn filter_confusables(pool: &[char], groups: &[&str]) -> Vec<char> {
+    pool.iter()
+        .copied()
+        .filter(|&ch| !groups.iter().any(|group| group.contains(ch)))
+        .collect()
+}
+
+fn chars_of(pool: &[u8]) -> Vec<char> {
+    pool.iter().map(|&byte| byte as char).collect()
+}
+
+fn filtered_pool(pool: &[char], exclude: &[char]) -> Vec<char> {
+    pool.iter()
+        .copied()
+        .filter(|ch| !exclude.contains(ch))
+        .collect()
+}
+
+struct Pools {
+    lowercase: Vec<char>,
+    uppercase: Vec<char>,
+    symbols: Vec<char>,
+    numbers: Vec<char>,
+}
+
+impl Pools {
+    fn categories(&self) -> [&[char]; 4] {
+        [
+            &self.lowercase,
+            &self.uppercase,
+            &self.symbols,
+            &self.numbers,
+        ]
     }
 
-    fn clamp_value(value: i32) -> i32 {
-        value.clamp(MIN_VALUE, MAX_VALUE)
+    fn new(symbol_set: &[char], exclude: &[char]) -> Self {
+        Self {
+            lowercase: filtered_pool(&chars_of(LETTERS), exclude),
+            uppercase: filtered_pool(&chars_of(&LETTERS.to_ascii_uppercase()), exclude),
+            symbols: filtered_pool(symbol_set, exclude),
+            numbers: filtered_pool(&chars_of(NUMBERS), exclude),
+        }
     }
 
-    fn update_value(&mut self, delta: i32) {
-        match self.focus {
-            0 => self.letters = Self::clamp_value(self.letters + delta),
-            1 => self.uppercase = Self::clamp_value(self.uppercase + delta),
-            2 => self.symbols = Self::clamp_value(self.symbols + delta),
-            3 => self.numbers = Self::clamp_value(self.numbers + delta),
-            _ => {}
+    fn for_class(&self, class: CharClass) -> &[char] {
+        match class {
+            CharClass::Letters => &self.lowercase,
+            CharClass::Uppercase => &self.uppercase,
+            CharClass::Symbols => &self.symbols,
+            CharClass::Numbers => &self.numbers,
         }
     }
 
-    fn clear_status_if_expired(&mut self) {
-        if let Some(deadline) = self.status_until {
-            if Instant::now() >= deadline {
-                self.status.clear();
-                self.status_until = None;
-            }
+    fn class_of(&self, ch: char) -> &[char] {
+        if ch.is_ascii_lowercase() {
+            &self.lowercase
+        } else if ch.is_ascii_uppercase() {
+            &self.uppercase
+        } else if ch.is_ascii_digit() {
+            &self.numbers
+        } else {
+            &self.symbols
         }
     }
 }
 
-#[cfg(unix)]
-fn copy_to_clipboard(value: &str) -> bool {
-    let mut child = match Command::new("wl-copy")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-    {
-        Ok(child) => child,
-        Err(_) => return false,
-    };
-
-    if let Some(mut stdin) = child.stdin.take() {
-        if stdin.write_all(value.as_bytes()).is_err() {
-            return false;
+fn parse_exclude(input: &str) -> Vec<char> {
+    let mut exclude = Vec::new();
+    for ch in sanitize_paste(TextField::Exclude, input).chars() {
+        if !exclude.contains(&ch) {
+            exclude.push(ch);
         }
-    } else {
-        return false;
     }
+    exclude
+}
 
-    child.wait().is_ok()
+#[derive(Debug, PartialEq, Eq)]
+enum SymbolSetError {
+    Empty,
+    NotAllowed(char),
+    Combining(char),
+    ZeroWidth(char),
 }
 
-#[cfg(windows)]
-fn copy_to_clipboard(value: &str) -> bool {
-    let mut clipboard = match Clipboard::new() {
-        Ok(clipboard) => clipboard,
-        Err(_) => return false,
-    };
+impl std::fmt::Display for SymbolSetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolSetError::Empty => write!(f, "The symbol set cannot be empty."),
+            SymbolSetError::NotAllowed(ch) => write!(f, "'{ch}' is not allowed in the symbol set."),
+            SymbolSetError::Combining(ch) => {
+                write!(f, "U+{:04X} is a combining mark, not a symbol.", *ch as u32)
+            }
+            SymbolSetError::ZeroWidth(ch) => {
+                write!(f, "U+{:04X} is invisible, not a symbol.", *ch as u32)
+            }
+        }
+    }
+}
+
+const ZERO_WIDTH: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
 
-    clipboard.set_text(value.to_string()).is_ok()
+fn sanitize_symbol_set(input: &str) -> Result<Vec<char>, SymbolSetError> {
+    let mut symbols = Vec::new();
+    // NFKC folds fullwidth and compatibility forms onto their plain symbol
+    for ch in input.nfkc() {
+        if ZERO_WIDTH.contains(&ch) {
+            return Err(SymbolSetError::ZeroWidth(ch));
+        }
+        if is_combining_mark(ch) {
+            return Err(SymbolSetError::Combining(ch));
+        }
+        if ch.is_alphanumeric() || ch.is_whitespace() || ch.is_control() {
+            return Err(SymbolSetError::NotAllowed(ch));
+        }
+        if !symbols.contains(&ch) {
+            symbols.push(ch);
+        }
+    }
+    if symbols.is_empty() {
+        return Err(SymbolSetError::Empty);
+    }
+    Ok(symbols)
 }
 
-#[cfg(not(any(unix, windows)))]
-fn copy_to_clipboard(_: &str) -> bool {
-    false
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct TextInput {
+    text: String,
+    // counted in chars, not bytes
+    cursor: usize,
 }
 
-fn ui(frame: &mut Frame, app: &App) {
-    let size = frame.area();
-    let outer = Block::default().borders(Borders::ALL).title("GenPass");
-    frame.render_widget(outer, size);
+impl TextInput {
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.chars().count(),
+        }
+    }
 
-    let inner = size.inner(Margin {
-        vertical: 1,
-        horizontal: 2,
-    });
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
 
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3),
-            Constraint::Length(7),
-            Constraint::Length(5),
-            Constraint::Length(5),
-            Constraint::Length(5),
-        ])
-        .split(inner);
+    fn insert(&mut self, ch: char) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert(index, ch);
+        self.cursor += 1;
+    }
 
-    let header = Paragraph::new(Line::from(vec![
-        Span::styled(
-            "Password Generator",
-            Style::default().add_modifier(Modifier::BOLD),
-        ),
-        Span::raw("  Use arrows (h, j, k, l) or +/- to adjust. Enter to generate."),
-    ]));
-    frame.render_widget(header, chunks[0]);
+    fn insert_str(&mut self, s: &str) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert_str(index, s);
+        self.cursor += s.chars().count();
+    }
 
-    let fields = [
-        ("Letters", app.letters),
-        ("Uppercase", app.uppercase),
-        ("Symbols", app.symbols),
-        ("Numbers", app.numbers),
-    ];
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.delete();
+        }
+    }
 
-    let field_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(1),
-        ])
-        .split(chunks[1]);
+    fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            self.text.remove(self.byte_index(self.cursor));
+        }
+    }
 
-    for (index, ((label, value), area)) in fields.iter().zip(field_chunks.iter()).enumerate() {
-        let is_active = index == app.focus;
-        let line = Line::from(vec![
-            Span::styled(
-                format!("{label:<10}"),
-                if is_active {
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default()
-                },
-            ),
-            Span::raw("  "),
-            Span::styled(
-                format!("{value:>3}"),
-                if is_active {
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::Gray)
-                },
-            ),
-        ]);
-        frame.render_widget(Paragraph::new(line), *area);
+    fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
     }
 
-    let actions = ["Generate", "Copy to clipboard", "Quit"];
-    let actions_block = Block::default().borders(Borders::ALL).title("Actions");
-    frame.render_widget(actions_block, chunks[2]);
-    let inner_actions = chunks[2].inner(Margin {
-        vertical: 1,
-        horizontal: 2,
-    });
-    let action_rows = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(1),
-        ])
-        .split(inner_actions);
+    fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
 
-    for (index, label) in actions.iter().enumerate() {
-        let focus_index = FOCUS_GENERATE + index;
-        let is_active = app.focus == focus_index;
-        let style = if is_active {
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::Gray)
-        };
-        let line = Line::from(vec![Span::styled(format!("> {label}"), style)]);
-        frame.render_widget(Paragraph::new(line), action_rows[index]);
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            KeyCode::Char(_) if modifiers.intersects(TYPING_MODIFIERS) => {}
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.chars().count(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Char(ch) => self.insert(ch),
+            _ => {}
+        }
     }
 
-    let strength_ratio = match app.strength.as_str() {
-        "Strong" => 1.0,
-        "Moderate" => 0.6,
-        "Weak" => 0.3,
-        "Do not use!!!!" => 0.0,
-        _ => 0.0,
-    };
+    fn spans(&self, style: Style) -> Vec<Span<'static>> {
+        let (before, rest) = self.text.split_at(self.byte_index(self.cursor));
+        let mut rest = rest.chars();
+        let under = rest.next().map_or(" ".to_string(), String::from);
+        vec![
+            Span::styled(before.to_string(), style),
+            Span::styled(under, style.add_modifier(Modifier::REVERSED)),
+            Span::styled(rest.collect::<String>(), style),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TextField {
+    Count,
+    Symbols,
+    Exclude,
+    Label,
+}
 
-    let strength_color = match app.strength.as_str() {
-        "Strong" => Color::Green,
-        "Moderate" => Color::Yellow,
-        "Weak" => Color::Red,
-        _ => Color::Gray,
+fn sanitize_paste(field: TextField, text: &str) -> String {
+    let allowed = |ch: char| match field {
+        TextField::Count => ch.is_ascii_digit(),
+        TextField::Symbols => !ch.is_alphanumeric() && !ch.is_whitespace() && !ch.is_control(),
+        TextField::Exclude => !ch.is_whitespace() && !ch.is_control(),
+        TextField::Label => !ch.is_control(),
     };
+    text.chars().filter(|&ch| allowed(ch)).collect()
+}
 
-    let gauge = Gauge::default()
-        .block(Block::default().borders(Borders::ALL).title("Strength"))
-        .gauge_style(Style::default().fg(strength_color))
-        .ratio(strength_ratio);
+#[cfg(test)]
+fn generate_password(
+    letters: i32,
+    uppercase: i32,
+    symbols: i32,
+    numbers: i32,
+    rng: &mut impl Rng,
+) -> String {
+    let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+    generate_from_pools(letters, uppercase, symbols, numbers, &pools, rng)
+}
 
-    let output = Paragraph::new(vec![
-        Line::from(vec![Span::styled(
-            "Generated Password",
-            Style::default().add_modifier(Modifier::BOLD),
-        )]),
-        Line::from(vec![Span::raw(&app.password)]),
-        Line::from(vec![Span::styled(
-            format!("Strength: {}", app.strength),
-            Style::default().fg(strength_color),
-        )]),
-    ])
-    .wrap(Wrap { trim: true })
-    .block(Block::default().borders(Borders::ALL).title("Output"));
+fn derive_password(master: &str, site: &str, spec: &PasswordSpec) -> String {
+    derive_password_with_rounds(master, site, spec, DERIVE_ROUNDS)
+}
 
-    // render widgets
-    frame.render_widget(gauge, chunks[4]);
-    frame.render_widget(output, chunks[3]);
+fn derive_password_with_rounds(
+    master: &str,
+    site: &str,
+    spec: &PasswordSpec,
+    rounds: u32,
+) -> String {
+    let counts = [spec.letters, spec.uppercase, spec.symbols, spec.numbers].map(|n| n.unwrap_or(0));
+    // the counts are salted in too, so changing the spec gives an unrelated password
+    let salt = format!(
+        "genpass:v{DERIVE_VERSION}:{}:{}",
+        site.trim().to_lowercase(),
+        counts.map(|n| n.to_string()).join(",")
+    );
+    let mut seed = [0; 32];
+    pbkdf2_hmac::<Sha256>(master.as_bytes(), salt.as_bytes(), rounds, &mut seed);
 
-    if !app.status.is_empty() {
-        let status_area = Rect {
-            x: inner.x,
-            y: inner.y + inner.height - 1,
-            width: inner.width,
-            height: 1,
-        };
-        frame.render_widget(
-            Paragraph::new(Line::from(Span::styled(
-                &app.status,
-                Style::default().fg(Color::Magenta),
-            ))),
-            status_area,
-        );
+    // everything below is spelled out rather than left to rand, whose sampling
+    // may change between releases: fixed alphabets, unbiased indices and our
+    // own Fisher-Yates shuffle
+    let upper = LETTERS.to_ascii_uppercase();
+    let alphabets: [&[u8]; 4] = [LETTERS, &upper, SYMBOLS, NUMBERS];
+    let mut stream = DeriveStream::new(seed);
+    let mut password = Vec::new();
+    for (count, alphabet) in counts.into_iter().zip(alphabets) {
+        for _ in 0..count.max(0) {
+            password.push(alphabet[stream.below(alphabet.len() as u32) as usize]);
+        }
+    }
+    for index in (1..password.len()).rev() {
+        password.swap(index, stream.below(index as u32 + 1) as usize);
     }
+    password.into_iter().map(char::from).collect()
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
-    let mut app = App::new();
+/// Expands the PBKDF2 seed into words: block `n` is SHA-256(seed || n as a
+/// big-endian u64), read four bytes at a time as big-endian u32s.
+struct DeriveStream {
+    seed: [u8; 32],
+    counter: u64,
+    block: [u8; 32],
+    used: usize,
+}
 
-    loop {
-        terminal.draw(|frame| ui(frame, &app))?;
-        app.clear_status_if_expired();
-
-        if event::poll(Duration::from_millis(50))? {
-            if let Event::Key(KeyEvent {
-                code,
-                modifiers,
-                kind,
-                ..
-            }) = event::read()?
-            {
-                if kind != KeyEventKind::Press {
-                    continue;
-                }
-                match (code, modifiers) {
-                    (KeyCode::Char('q'), _) | (KeyCode::Esc, _) => return Ok(()),
-                    (KeyCode::Up, _) | (KeyCode::Char('k'), _) => {
-                        app.focus = app.focus.saturating_sub(1);
-                    }
-                    (KeyCode::Down, _) | (KeyCode::Char('j'), _) => {
-                        app.focus = (app.focus + 1).min(FOCUS_QUIT);
-                    }
-                    (KeyCode::Left, _) | (KeyCode::Char('-'), _) | (KeyCode::Char('h'), _) => {
-                        app.update_value(-1);
-                    }
-                    (KeyCode::Right, _)
-                    | (KeyCode::Char('+'), _)
-                    | (KeyCode::Char('='), _)
-                    | (KeyCode::Char('l'), _) => {
-                        app.update_value(1);
-                    }
-                    (KeyCode::Char('g'), _) | (KeyCode::Enter, _) => {
-                        if app.focus >= FOCUS_FIELDS {
-                            match app.focus {
-                                FOCUS_GENERATE => {
-                                    app.generate_password();
-                                    terminal.draw(|frame| ui(frame, &app))?;
-                                }
-                                FOCUS_COPY => {
-                                    if copy_to_clipboard(&app.password) {
-                                        app.status = "Copied to clipboard.".to_string();
-                                    } else {
-                                        app.status = "Clipboard unavailable.".to_string();
-                                    }
-                                    app.status_until =
-                                        Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
-                                    terminal.draw(|frame| ui(frame, &app))?;
-                                }
-                                FOCUS_QUIT => return Ok(()),
-                                _ => {}
-                            }
-                        } else {
-                            app.generate_password();
-                            terminal.draw(|frame| ui(frame, &app))?;
-                        }
-                    }
-                    (KeyCode::Char('c'), _) | (KeyCode::Char('C'), _) => {
-                        if copy_to_clipboard(&app.password) {
-                            app.status = "Copied to clipboard.".to_string();
-                        } else {
-                            app.status = "Clipboard unavailable.".to_string();
-                        }
-                        app.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
-                        terminal.draw(|frame| ui(frame, &app))?;
-                    }
-                    (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
-                        app.generate_password();
-                        terminal.draw(|frame| ui(frame, &app))?;
-                    }
-                    _ => {}
-                }
+impl DeriveStream {
+    fn new(seed: [u8; 32]) -> Self {
+        DeriveStream {
+            seed,
+            counter: 0,
+            block: [0; 32],
+            used: 32,
+        }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.used == self.block.len() {
+            let mut hasher = Sha256::new();
+            hasher.update(self.seed);
+            hasher.update(self.counter.to_be_bytes());
+            self.block = hasher.finalize().into();
+            self.counter += 1;
+            self.used = 0;
+        }
+        let word = &self.block[self.used..self.used + 4];
+        self.used += 4;
+        u32::from_be_bytes([word[0], word[1], word[2], word[3]])
+    }
+
+    /// A uniform index below `n`, rejecting the words past the last whole
+    /// multiple of `n` instead of taking a biased remainder.
+    fn below(&mut self, n: u32) -> u32 {
+        let zone = (1u64 << 32) - (1u64 << 32) % u64::from(n);
+        loop {
+            let word = self.next_u32();
+            if u64::from(word) < zone {
+                return word % n;
             }
         }
     }
 }
 
-fn main() -> io::Result<()> {
+/// Prompts on stderr and reads the master password with echo off.
+fn read_hidden_master() -> io::Result<String> {
+    eprint!("Master password: ");
+    io::stderr().flush()?;
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
+    let master = read_hidden_line();
+    disable_raw_mode()?;
+    eprintln!();
+    master
+}
+
+fn read_hidden_line() -> io::Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            KeyCode::Char(ch) => line.push(ch),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn run_derive(site: &str, spec: &PasswordSpec, input: &mut impl io::Read) -> i32 {
+    let mut buffer = String::new();
+    if let Err(err) = input.read_to_string(&mut buffer) {
+        eprintln!("genpass: could not read stdin: {err}");
+        return 2;
+    }
+    let master = buffer.trim_end_matches(['\r', '\n']);
+    if master.is_empty() {
+        eprintln!("genpass: --derive expects the master password on stdin");
+        return 2;
+    }
+    println!("{}", derive_password(master, site, spec));
+    0
+}
+
+fn generate_from_pools(
+    letters: i32,
+    uppercase: i32,
+    symbols: i32,
+    numbers: i32,
+    pools: &Pools,
+    rng: &mut impl Rng,
+) -> String {
+    let mut generated = Vec::new();
+    bulk::fill_from_pools(
+        &mut generated,
+        [letters, uppercase, symbols, numbers],
+        pools.categories(),
+        &mut Vec::new(),
+        rng,
+    );
+    generated.into_iter().collect()
+}
+
+fn generate_distinct(counts: [i32; 4], pools: &Pools, rng: &mut impl Rng) -> String {
+    let categories = [
+        &pools.lowercase,
+        &pools.uppercase,
+        &pools.symbols,
+        &pools.numbers,
+    ];
+    let mut generated = Vec::new();
+    for (count, pool) in counts.into_iter().zip(categories) {
+        generated.extend(pool.sample(rng, count.max(0) as usize).copied());
+    }
+    generated.shuffle(rng);
+    generated.into_iter().collect()
+}
+
+fn generate_many(
+    spec: &PasswordSpec,
+    pools: &Pools,
+    count: usize,
+    rng: &mut impl Rng,
+    mut progress: impl FnMut(usize),
+) -> Vec<String> {
+    let counts = [spec.letters, spec.uppercase, spec.symbols, spec.numbers].map(|n| n.unwrap_or(0));
+    bulk::generate_many(counts, pools.categories(), count, rng, |done| {
+        report_progress(done, count, &mut progress)
+    })
+}
+
+fn report_progress(done: usize, total: usize, progress: &mut impl FnMut(usize)) {
+    if done.is_multiple_of(PROGRESS_STEP) || done == total {
+        progress(done);
+    }
+}
+
+fn generate_pronounceable(syllables: usize, rng: &mut impl Rng) -> String {
+    let mut generated = String::with_capacity(syllables * 2);
+
+    for _ in 0..syllables {
+        generated.push(*CONSONANTS.choose(rng).unwrap() as char);
+        generated.push(*VOWELS.choose(rng).unwrap() as char);
+    }
+
+    generated
+}
+
+fn generate_pronounceable_password(
+    syllables: i32,
+    uppercase: i32,
+    symbols: i32,
+    numbers: i32,
+    pools: &Pools,
+    rng: &mut impl Rng,
+) -> String {
+    let mut generated: Vec<char> = generate_pronounceable(syllables.max(0) as usize, rng)
+        .chars()
+        .collect();
+
+    let mut positions: Vec<usize> = (0..generated.len()).collect();
+    positions.shuffle(rng);
+    for &index in positions.iter().take(uppercase.max(0) as usize) {
+        generated[index] = generated[index].to_ascii_uppercase();
+    }
+
+    for _ in 0..symbols {
+        if let Some(ch) = pools.symbols.choose(rng) {
+            generated.push(*ch);
+        }
+    }
+    for _ in 0..numbers {
+        if let Some(ch) = pools.numbers.choose(rng) {
+            generated.push(*ch);
+        }
+    }
+
+    generated.into_iter().collect()
+}
+
+fn bits(count: i32, pool: usize) -> f64 {
+    if pool <= 1 {
+        0.0
+    } else {
+        count.max(0) as f64 * (pool as f64).log2()
+    }
+}
+
+fn spec_entropy_bits(spec: &PasswordSpec, pools: &Pools) -> f64 {
+    bits(spec.letters.unwrap_or(0), pools.lowercase.len())
+        + bits(spec.uppercase.unwrap_or(0), pools.uppercase.len())
+        + bits(spec.symbols.unwrap_or(0), pools.symbols.len())
+        + bits(spec.numbers.unwrap_or(0), pools.numbers.len())
+}
+
+fn strength_color_for(strength: Strength, theme: &Theme) -> Color {
+    match strength {
+        Strength::Strong => theme.good,
+        Strength::Moderate => theme.warn,
+        Strength::Weak => theme.bad,
+        Strength::DoNotUse => theme.inactive,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Thresholds {
+    warn: f64,
+    good: f64,
+    full: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            warn: 40.0,
+            good: 60.0,
+            full: 80.0,
+        }
+    }
+}
+
+impl Thresholds {
+    fn is_valid(&self) -> bool {
+        0.0 < self.warn && self.warn < self.good && self.good <= self.full
+    }
+}
+
+fn gauge_style(bits: f64, thresholds: &Thresholds, theme: &Theme) -> (Color, f64) {
+    let color = if bits < thresholds.warn {
+        theme.bad
+    } else if bits < thresholds.good {
+        theme.warn
+    } else {
+        theme.good
+    };
+    (color, (bits / thresholds.full).clamp(0.0, 1.0))
+}
+
+fn target_bits(strength: Strength) -> Option<f64> {
+    // the lower bounds used by entropy_strength
+    match strength {
+        Strength::DoNotUse => None,
+        Strength::Weak => Some(28.0),
+        Strength::Moderate => Some(40.0),
+        Strength::Strong => Some(60.0),
+    }
+}
+
+fn marker_x(rect: Rect, ratio: f64) -> u16 {
+    let span = rect.width.saturating_sub(1) as f64;
+    rect.x + (ratio.clamp(0.0, 1.0) * span).round() as u16
+}
+
+fn bits_per_char(pool_size: usize) -> f64 {
+    bits(1, pool_size)
+}
+
+fn entropy_advice(app: &App) -> Option<String> {
+    let pools = app.pools();
+    let (pool_size, unit, class) = match (app.mode, app.focus) {
+        (Mode::Random, 0) => (pools.lowercase.len(), "char", "letters"),
+        (Mode::Random | Mode::Pronounceable, 1) => (pools.uppercase.len(), "char", "uppercase"),
+        (Mode::Random | Mode::Pronounceable, 2) => (pools.symbols.len(), "char", "symbols"),
+        (Mode::Random | Mode::Pronounceable, 3) => (pools.numbers.len(), "char", "numbers"),
+        (Mode::Pin, 0) => (pools.numbers.len(), "digit", "digits"),
+        (Mode::Passphrase, 0) => (wordlist().len(), "word", "words"),
+        (Mode::Combo, 0) => (pools.numbers.len(), "digit", "digits"),
+        _ => return None,
+    };
+    Some(format!(
+        "+{:.1} bits/{unit} for {class}",
+        bits_per_char(pool_size)
+    ))
+}
+
+fn estimate_entropy(app: &App) -> f64 {
+    entropy_for(app, app.split_affixes(&app.password).1)
+}
+
+/// Entropy of `core` under the app's settings; only passphrase styling depends
+/// on the password itself.
+fn entropy_for(app: &App, core: &str) -> f64 {
+    let pools = app.pools();
+    match app.mode {
+        Mode::Random => spec_entropy_bits(&app.spec(), &pools),
+        // each syllable only draws from the consonant and vowel alphabets
+        Mode::Pronounceable => {
+            bits(app.letters, CONSONANTS.len())
+                + bits(app.letters, VOWELS.len())
+                + bits(app.symbols, pools.symbols.len())
+                + bits(app.numbers, pools.numbers.len())
+        }
+        Mode::Pin => bits(app.pin_digits, pools.numbers.len()),
+        Mode::Passphrase => {
+            bits(app.passphrase_words, wordlist().len())
+                + passphrase_style_bits(&app.passphrase_style, core, pools.symbols.len())
+        }
+        Mode::Combo => word_combo_bits(wordlist().len(), &pools, app.combo_digits),
+    }
+}
+
+fn wordlist() -> Vec<&'static str> {
+    WORDLIST.lines().map(str::trim).collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum WordlistError {
+    EmptyEntry(usize),
+    Duplicate(String),
+    TooShort(usize),
+}
+
+impl std::fmt::Display for WordlistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WordlistError::EmptyEntry(line) => {
+                write!(f, "the wordlist has an empty entry on line {line}")
+            }
+            WordlistError::Duplicate(word) => {
+                write!(f, "the wordlist lists \"{word}\" more than once")
+            }
+            WordlistError::TooShort(len) => write!(
+                f,
+                "the wordlist has {len} words, at least {MIN_WORDLIST_WORDS} are needed"
+            ),
+        }
+    }
+}
+
+fn validate_wordlist(words: &[&str]) -> Result<(), WordlistError> {
+    let mut seen = HashSet::new();
+    for (index, word) in words.iter().enumerate() {
+        if word.is_empty() {
+            return Err(WordlistError::EmptyEntry(index + 1));
+        }
+        if !seen.insert(word) {
+            return Err(WordlistError::Duplicate(word.to_string()));
+        }
+    }
+    if words.len() < MIN_WORDLIST_WORDS {
+        return Err(WordlistError::TooShort(words.len()));
+    }
+    Ok(())
+}
+
+fn generate_passphrase(words: i32, list: &[&str], rng: &mut impl Rng) -> String {
+    (0..words)
+        .filter_map(|_| list.choose(rng).copied())
+        .collect::<Vec<_>>()
+        .join(&PASSPHRASE_SEPARATOR.to_string())
+}
+
+fn word_combo_bits(list_len: usize, pools: &Pools, digits: i32) -> f64 {
+    bits(COMBO_WORDS, list_len) + bits(1, pools.symbols.len()) + bits(digits, pools.numbers.len())
+}
+
+fn generate_word_combo(
+    words: &[&str],
+    pools: &Pools,
+    digits: i32,
+    rng: &mut impl Rng,
+) -> (String, f64) {
+    let mut combo = String::new();
+    for index in 0..COMBO_WORDS {
+        let Some(word) = words.choose(rng) else {
+            break;
+        };
+        let mut chars = word.chars();
+        if index == 0
+            && let Some(first) = chars.next()
+        {
+            combo.push(first.to_ascii_uppercase());
+        }
+        combo.extend(chars);
+        if index == 0 {
+            combo.extend(pools.symbols.choose(rng));
+        }
+    }
+    combo.extend((0..digits).filter_map(|_| pools.numbers.choose(rng)));
+    (combo, word_combo_bits(words.len(), pools, digits))
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct PassphraseStyle {
+    title_case: bool,
+    capitalize_one: bool,
+    append_number: bool,
+    insert_symbol: bool,
+}
+
+fn apply_passphrase_style(
+    phrase: &str,
+    style: &PassphraseStyle,
+    symbols: &[char],
+    rng: &mut impl Rng,
+) -> String {
+    let mut chars: Vec<char> = phrase.chars().collect();
+    if style.title_case {
+        for index in 0..chars.len() {
+            if index == 0 || chars[index - 1] == PASSPHRASE_SEPARATOR {
+                chars[index] = chars[index].to_ascii_uppercase();
+            }
+        }
+    }
+    if style.capitalize_one {
+        let lowercase: Vec<usize> = (0..chars.len())
+            .filter(|&index| chars[index].is_ascii_lowercase())
+            .collect();
+        if let Some(&index) = lowercase.choose(rng) {
+            chars[index] = chars[index].to_ascii_uppercase();
+        }
+    }
+    if style.insert_symbol {
+        let gaps: Vec<usize> = (0..chars.len())
+            .filter(|&index| chars[index] == PASSPHRASE_SEPARATOR)
+            .collect();
+        if let (Some(&index), Some(&symbol)) = (gaps.choose(rng), symbols.choose(rng)) {
+            chars[index] = symbol;
+        }
+    }
+    let mut styled: String = chars.into_iter().collect();
+    if style.append_number {
+        styled.push_str(&format!("{:02}", rng.random_range(0..100)));
+    }
+    styled
+}
+
+fn passphrase_style_bits(style: &PassphraseStyle, phrase: &str, symbol_count: usize) -> f64 {
+    let words = count_words(phrase);
+    let mut total = 0.0;
+    if style.capitalize_one {
+        let letters = phrase.chars().filter(char::is_ascii_alphabetic).count();
+        // title case already fixed the first letter of every word
+        let candidates = letters.saturating_sub(if style.title_case { words } else { 0 });
+        total += bits(1, candidates);
+    }
+    if style.append_number {
+        total += bits(1, 100);
+    }
+    if style.insert_symbol && words > 1 {
+        total += bits(1, words - 1) + bits(1, symbol_count);
+    }
+    total
+}
+
+fn count_words(phrase: &str) -> usize {
+    phrase
+        .split(|ch: char| !ch.is_ascii_alphabetic())
+        .filter(|word| !word.is_empty())
+        .count()
+}
+
+fn entropy_strength(bits: f64) -> Strength {
+    if bits >= 60.0 {
+        Strength::Strong
+    } else if bits >= 40.0 {
+        Strength::Moderate
+    } else if bits >= 28.0 {
+        Strength::Weak
+    } else {
+        Strength::DoNotUse
+    }
+}
+
+fn passphrase_strength(words: i32, list_len: usize, style_bits: f64) -> Strength {
+    entropy_strength(bits(words, list_len) + style_bits)
+}
+
+fn strength_for_mode(app: &App, password: &str) -> Strength {
+    if let Some(common) = &app.common
+        && is_common_password(password, common)
+    {
+        return Strength::DoNotUse;
+    }
+    match app.mode {
+        // lowercase-only words fail the class checks despite their entropy
+        Mode::Passphrase => {
+            let words = count_words(password) as i32;
+            let style_bits =
+                passphrase_style_bits(&app.passphrase_style, password, app.pools().symbols.len());
+            passphrase_strength(words, wordlist().len(), style_bits)
+        }
+        Mode::Combo => entropy_strength(word_combo_bits(
+            wordlist().len(),
+            &app.pools(),
+            app.combo_digits,
+        )),
+        _ => check_password_strength(password, app.strength_min_length),
+    }
+}
+
+fn generate_pin(digits: i32, pools: &Pools, rng: &mut impl Rng) -> String {
+    (0..digits)
+        .filter_map(|_| pools.numbers.choose(rng).copied())
+        .collect()
+}
+
+fn group(s: &str, n: usize, sep: char) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    chars
+        .chunks(n.max(1))
+        .map(|chunk| chunk.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(&sep.to_string())
+}
+
+fn is_sequence(a: char, b: char, c: char) -> bool {
+    let first = b as i64 - a as i64;
+    let second = c as i64 - b as i64;
+    first == second && first.abs() == 1
+}
+
+fn find_sequence(password: &[char]) -> Option<usize> {
+    password
+        .windows(3)
+        .position(|w| is_sequence(w[0], w[1], w[2]))
+}
+
+fn swap_keeps_layout(password: &[char], layout: &[Option<CharClass>], a: usize, b: usize) -> bool {
+    let fits = |slot: usize, ch: char| {
+        layout
+            .get(slot)
+            .copied()
+            .flatten()
+            .is_none_or(|class| CharClass::of(ch) == class)
+    };
+    fits(a, password[b]) && fits(b, password[a])
+}
+
+/// Breaks up runs like `abc` or `321`, returning false if one is left.
+/// With `distinct`, a redrawn character never repeats one already present.
+fn remove_sequences(
+    password: &mut [char],
+    layout: &[Option<CharClass>],
+    pools: &Pools,
+    distinct: bool,
+    rng: &mut impl Rng,
+) -> bool {
+    const MAX_SWAPS: usize = 1000;
+    const MAX_REDRAWS: usize = 100;
+
+    for attempt in 0..MAX_SWAPS + MAX_REDRAWS {
+        let Some(index) = find_sequence(password) else {
+            return true;
+        };
+        let offender = index + 2;
+
+        if attempt < MAX_SWAPS {
+            let other = rng.random_range(0..password.len());
+            if swap_keeps_layout(password, layout, offender, other) {
+                password.swap(offender, other);
+            }
+        } else {
+            // swapping alone can't always break a run, so redraw from the same class
+            let fresh: Vec<char> = pools
+                .class_of(password[offender])
+                .iter()
+                .copied()
+                .filter(|ch| !distinct || !password.contains(ch))
+                .collect();
+            if let Some(&ch) = fresh.choose(rng) {
+                password[offender] = ch;
+            }
+        }
+    }
+    find_sequence(password).is_none()
+}
+
+fn parse_layout(input: &str) -> Option<Vec<Option<CharClass>>> {
+    input
+        .chars()
+        .map(|ch| match ch {
+            'l' => Some(Some(CharClass::Letters)),
+            'u' => Some(Some(CharClass::Uppercase)),
+            's' => Some(Some(CharClass::Symbols)),
+            'n' => Some(Some(CharClass::Numbers)),
+            '?' => Some(None),
+            _ => None,
+        })
+        .collect()
+}
+
+fn format_layout(layout: &[Option<CharClass>]) -> String {
+    layout
+        .iter()
+        .map(|class| class.map_or('?', CharClass::layout_char))
+        .collect()
+}
+
+fn apply_layout(
+    password: &mut Vec<char>,
+    layout: &[Option<CharClass>],
+    pools: &Pools,
+    rng: &mut impl Rng,
+) {
+    let mut spare = std::mem::take(password);
+    let mut fixed = Vec::with_capacity(spare.len());
+    for position in 0..spare.len() {
+        let Some(class) = layout.get(position).copied().flatten() else {
+            fixed.push(None);
+            continue;
+        };
+        // prefer a character already drawn for this class so the counts hold
+        let ch = match spare.iter().position(|&ch| CharClass::of(ch) == class) {
+            Some(index) => Some(spare.remove(index)),
+            None => pools.for_class(class).choose(rng).copied(),
+        };
+        fixed.push(ch);
+    }
+    let mut rest = spare.into_iter();
+    *password = fixed
+        .into_iter()
+        .filter_map(|slot| slot.or_else(|| rest.next()))
+        .collect();
+}
+
+fn ensure_leading_letter(password: &mut [char], layout: &[Option<CharClass>]) -> bool {
+    let letter = (0..password.len()).find(|&index| {
+        password[index].is_alphabetic() && swap_keeps_layout(password, layout, 0, index)
+    });
+    match letter {
+        Some(index) => {
+            password.swap(0, index);
+            true
+        }
+        None => false,
+    }
+}
+
+fn find_class_run(password: &[char], class: CharClass, cap: usize) -> Option<usize> {
+    let mut run = 0;
+    for (index, &ch) in password.iter().enumerate() {
+        run = if CharClass::of(ch) == class {
+            run + 1
+        } else {
+            0
+        };
+        if run > cap {
+            return Some(index);
+        }
+    }
+    None
+}
+
+fn break_class_runs(
+    password: &mut [char],
+    layout: &[Option<CharClass>],
+    class: CharClass,
+    cap: usize,
+    rng: &mut impl Rng,
+) -> bool {
+    const MAX_SWAPS: usize = 1000;
+
+    let members = password
+        .iter()
+        .filter(|&&ch| CharClass::of(ch) == class)
+        .count();
+    let others = password.len() - members;
+    if members > cap * (others + 1) {
+        return false;
+    }
+    for _ in 0..MAX_SWAPS {
+        let Some(offender) = find_class_run(password, class, cap) else {
+            return true;
+        };
+        let outside: Vec<usize> = (0..password.len())
+            .filter(|&index| {
+                CharClass::of(password[index]) != class
+                    && swap_keeps_layout(password, layout, offender, index)
+            })
+            .collect();
+        if let Some(&other) = outside.choose(rng) {
+            password.swap(offender, other);
+        }
+    }
+    if find_class_run(password, class, cap).is_none() {
+        return true;
+    }
+    if !layout.is_empty() {
+        // the even spread below would move characters the layout pinned
+        return false;
+    }
+    // random swaps keep missing, so lay the runs out evenly between the other characters
+    let (mut inside, mut rest): (Vec<char>, Vec<char>) =
+        password.iter().partition(|&&ch| CharClass::of(ch) == class);
+    let mut rebuilt = Vec::with_capacity(password.len());
+    while !inside.is_empty() || !rest.is_empty() {
+        let take = inside.len().min(cap);
+        rebuilt.extend(inside.drain(..take));
+        rebuilt.extend(rest.pop());
+    }
+    password.copy_from_slice(&rebuilt);
+    true
+}
+
+fn pool_sufficient(count: i32, pool_size: usize) -> bool {
+    count <= 0 || count as usize <= pool_size
+}
+
+fn reroll_class(
+    password: &mut [char],
+    class: CharClass,
+    pools: &Pools,
+    distinct: bool,
+    rng: &mut impl Rng,
+) {
+    let pool = pools.for_class(class);
+    // the pools are disjoint, so each character still tells which class drew it
+    let positions: Vec<usize> = (0..password.len())
+        .filter(|&index| CharClass::of(password[index]) == class)
+        .collect();
+    // other classes can't collide, so distinct only needs a draw without replacement
+    let fresh: Vec<char> = if distinct {
+        pool.sample(rng, positions.len()).copied().collect()
+    } else {
+        positions
+            .iter()
+            .filter_map(|_| pool.choose(rng).copied())
+            .collect()
+    };
+    for (&index, &ch) in positions.iter().zip(&fresh) {
+        password[index] = ch;
+    }
+}
+
+fn avoid_leading_zero(
+    password: &mut [char],
+    layout: &[Option<CharClass>],
+    pools: &Pools,
+    rng: &mut impl Rng,
+) -> bool {
+    if password.first() != Some(&'0') {
+        return true;
+    }
+    if let Some(index) = (0..password.len())
+        .find(|&index| password[index] != '0' && swap_keeps_layout(password, layout, 0, index))
+    {
+        password.swap(0, index);
+        return true;
+    }
+    // every character is a zero, so draw a new first digit
+    let nonzero: Vec<char> = pools
+        .numbers
+        .iter()
+        .copied()
+        .filter(|&ch| ch != '0')
+        .collect();
+    match nonzero.choose(rng) {
+        Some(&digit) => {
+            password[0] = digit;
+            true
+        }
+        None => false,
+    }
+}
+
+fn render_qr(value: &str) -> Result<Vec<String>, String> {
+    let code = QrCode::new(value.as_bytes()).map_err(|_| "Password too long for a QR code.")?;
+    let width = code.width();
+    if width > QR_MAX_WIDTH {
+        return Err("Password too long for the QR view.".to_string());
+    }
+
+    let colors = code.to_colors();
+    let size = width + QR_QUIET_ZONE * 2;
+    // light modules are drawn filled so the code reads correctly on dark terminals
+    let is_light = |x: usize, y: usize| {
+        if x < QR_QUIET_ZONE || y < QR_QUIET_ZONE {
+            return true;
+        }
+        let (x, y) = (x - QR_QUIET_ZONE, y - QR_QUIET_ZONE);
+        x >= width || y >= width || colors[y * width + x] == QrColor::Light
+    };
+
+    let lines = (0..size)
+        .step_by(2)
+        .map(|y| {
+            (0..size)
+                .map(
+                    |x| match (is_light(x, y), y + 1 < size && is_light(x, y + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    },
+                )
+                .collect()
+        })
+        .collect();
+
+    Ok(lines)
+}
+
+fn diff_mask(previous: &str, current: &str) -> Vec<bool> {
+    let mut previous = previous.chars();
+    current
+        .chars()
+        .map(|ch| previous.next() != Some(ch))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ThemeKind {
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl ThemeKind {
+    fn label(self) -> &'static str {
+        match self {
+            ThemeKind::Dark => "dark",
+            ThemeKind::Light => "light",
+            ThemeKind::HighContrast => "high-contrast",
+        }
+    }
+
+    fn parse(value: &str) -> Option<ThemeKind> {
+        match value.to_ascii_lowercase().as_str() {
+            "dark" => Some(ThemeKind::Dark),
+            "light" => Some(ThemeKind::Light),
+            "high-contrast" | "highcontrast" => Some(ThemeKind::HighContrast),
+            _ => None,
+        }
+    }
+
+    fn next(self) -> ThemeKind {
+        match self {
+            ThemeKind::Dark => ThemeKind::Light,
+            ThemeKind::Light => ThemeKind::HighContrast,
+            ThemeKind::HighContrast => ThemeKind::Dark,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Theme {
+    active: Color,
+    inactive: Color,
+    good: Color,
+    warn: Color,
+    bad: Color,
+    status: Color,
+    flash: Color,
+    lowercase: Color,
+    uppercase: Color,
+    digit: Color,
+    symbol: Color,
+}
+
+fn palette(kind: ThemeKind) -> Theme {
+    match kind {
+        ThemeKind::Dark => Theme {
+            active: Color::Yellow,
+            inactive: Color::Gray,
+            good: Color::Green,
+            warn: Color::Yellow,
+            bad: Color::Red,
+            status: Color::Magenta,
+            flash: Color::LightCyan,
+            lowercase: Color::White,
+            uppercase: Color::Cyan,
+            digit: Color::Yellow,
+            symbol: Color::Magenta,
+        },
+        ThemeKind::Light => Theme {
+            active: Color::Blue,
+            inactive: Color::DarkGray,
+            good: Color::Rgb(0, 128, 0),
+            warn: Color::Rgb(176, 112, 0),
+            bad: Color::Rgb(192, 0, 0),
+            status: Color::Rgb(128, 0, 128),
+            flash: Color::Rgb(0, 96, 192),
+            lowercase: Color::Black,
+            uppercase: Color::Blue,
+            digit: Color::Rgb(176, 112, 0),
+            symbol: Color::Rgb(128, 0, 128),
+        },
+        ThemeKind::HighContrast => Theme {
+            active: Color::LightYellow,
+            inactive: Color::White,
+            good: Color::LightGreen,
+            warn: Color::LightYellow,
+            bad: Color::LightRed,
+            status: Color::LightMagenta,
+            flash: Color::LightCyan,
+            lowercase: Color::White,
+            uppercase: Color::LightCyan,
+            digit: Color::LightYellow,
+            symbol: Color::LightMagenta,
+        },
+    }
+}
+
+fn char_class_color(ch: char, theme: &Theme) -> Color {
+    if ch.is_ascii_lowercase() {
+        theme.lowercase
+    } else if ch.is_ascii_uppercase() {
+        theme.uppercase
+    } else if ch.is_ascii_digit() {
+        theme.digit
+    } else {
+        theme.symbol
+    }
+}
+
+fn class_colored_spans(password: &str, theme: &Theme) -> Vec<Span<'static>> {
+    password
+        .chars()
+        .map(|ch| {
+            Span::styled(
+                ch.to_string(),
+                Style::default().fg(char_class_color(ch, theme)),
+            )
+        })
+        .collect()
+}
+
+fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("genpass"));
+    }
+    let base = if cfg!(windows) {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?).join(".config")
+    };
+    Some(base.join("genpass"))
+}
+
+fn settings_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("settings.toml"))
+}
+
+fn profiles_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("profiles.toml"))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct KeyCombo {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyCombo {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // terminals disagree on reporting shift for characters and backtab
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Action {
+    Generate,
+    Copy,
+    Quit,
+    Inc,
+    Dec,
+    FocusUp,
+    FocusDown,
+}
+
+impl Action {
+    fn parse(name: &str) -> Option<Action> {
+        match name {
+            "generate" => Some(Action::Generate),
+            "copy" => Some(Action::Copy),
+            "quit" => Some(Action::Quit),
+            "inc" => Some(Action::Inc),
+            "dec" => Some(Action::Dec),
+            "focus_up" => Some(Action::FocusUp),
+            "focus_down" => Some(Action::FocusDown),
+            _ => None,
+        }
+    }
+}
+
+fn parse_key(s: &str) -> Option<KeyCombo> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    let mut modifiers = KeyModifiers::NONE;
+    let mut parts: Vec<&str> = s.split('+').collect();
+    // "+" on its own, or as the last key of "ctrl++", leaves empty parts
+    let key = match parts.pop()? {
+        "" if parts.last() == Some(&"") || parts.is_empty() => {
+            parts.pop();
+            "+"
+        }
+        key => key,
+    };
+    for part in parts {
+        modifiers |= match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+    }
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(ch), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyCode::Char(ch.to_ascii_uppercase())
+        }
+        (Some(ch), None) => KeyCode::Char(ch),
+        _ => match key.to_ascii_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+        },
+    };
+    Some(KeyCombo::new(code, modifiers))
+}
+
+fn wipe(secret: &mut String) {
+    let mut bytes = std::mem::take(secret).into_bytes();
+    bytes.fill(0);
+    // keep the zeroing from being optimized away before the buffer is freed
+    std::hint::black_box(&bytes);
+}
+
+fn describe_key(code: KeyCode, modifiers: KeyModifiers) -> String {
+    let mut out = String::new();
+    for (modifier, name) in [
+        (KeyModifiers::CONTROL, "Ctrl+"),
+        (KeyModifiers::ALT, "Alt+"),
+        (KeyModifiers::SHIFT, "Shift+"),
+    ] {
+        if modifiers.contains(modifier) {
+            out.push_str(name);
+        }
+    }
+    match code {
+        KeyCode::Char(ch) => out.push(ch),
+        KeyCode::F(n) => out.push_str(&format!("F{n}")),
+        code => out.push_str(&format!("{code:?}")),
+    }
+    out
+}
+
+fn relaxed_motion(
+    keymap: &HashMap<KeyCombo, Action>,
+    code: KeyCode,
+    modifiers: KeyModifiers,
+) -> Option<Action> {
+    // caps lock turns h into H, and some terminals add stray modifiers
+    let lowered = match code {
+        KeyCode::Char(ch) => KeyCode::Char(ch.to_ascii_lowercase()),
+        code => code,
+    };
+    let candidates = [
+        KeyCombo::new(code, KeyModifiers::NONE),
+        KeyCombo::new(lowered, KeyModifiers::NONE),
+    ];
+    if candidates[0] == KeyCombo::new(code, modifiers) && lowered == code {
+        return None;
+    }
+    candidates
+        .iter()
+        .find_map(|combo| keymap.get(combo).copied())
+        .filter(|action| {
+            matches!(
+                action,
+                Action::Inc | Action::Dec | Action::FocusUp | Action::FocusDown
+            )
+        })
+}
+
+fn accelerator(code: KeyCode) -> Option<Action> {
+    // unlike g and enter these ignore which action button has focus
+    match code {
+        KeyCode::Char('R') => Some(Action::Generate),
+        KeyCode::Char('Y') => Some(Action::Copy),
+        _ => None,
+    }
+}
+
+fn default_keymap() -> HashMap<KeyCombo, Action> {
+    let defaults: [(Action, &[&str]); 7] = [
+        (Action::Generate, &["g", "enter"]),
+        (Action::Copy, &["c", "C"]),
+        (Action::Quit, &["q", "esc"]),
+        (Action::Inc, &["right", "+", "=", "l"]),
+        (Action::Dec, &["left", "-", "h"]),
+        (Action::FocusUp, &["up", "k"]),
+        (Action::FocusDown, &["down", "j"]),
+    ];
+    defaults
+        .into_iter()
+        .flat_map(|(action, keys)| {
+            keys.iter()
+                .filter_map(move |key| Some((parse_key(key)?, action)))
+        })
+        .collect()
+}
+
+fn parse_keymap(contents: &str) -> HashMap<KeyCombo, Action> {
+    let mut keymap = default_keymap();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let Some(action) = Action::parse(name.trim()) else {
+            continue;
+        };
+        let keys: Vec<KeyCombo> = parse_key_list(value)
+            .iter()
+            .filter_map(|key| parse_key(key))
+            .collect();
+        if keys.is_empty() {
+            continue;
+        }
+        // a configured action replaces its defaults rather than adding to them
+        keymap.retain(|_, bound| *bound != action);
+        for key in keys {
+            keymap.insert(key, action);
+        }
+    }
+    keymap
+}
+
+fn parse_key_list(value: &str) -> Vec<String> {
+    // a quoted "," or "]" is a key, so only split on commas outside quotes
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|inner| inner.strip_suffix(']'))
+        .unwrap_or(value);
+    let mut keys = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if quoted => current.extend(chars.next()),
+            '"' => quoted = !quoted,
+            ',' if !quoted => keys.push(std::mem::take(&mut current)),
+            ch => current.push(ch),
+        }
+    }
+    keys.push(current);
+    keys
+}
+
+fn key_hints(keymap: &HashMap<KeyCombo, Action>) -> String {
+    [
+        (Action::Generate, "generate"),
+        (Action::Copy, "copy"),
+        (Action::Quit, "quit"),
+    ]
+    .into_iter()
+    .filter_map(|(action, name)| {
+        // the plainest binding reads best: no modifiers, shortest, lowercase first
+        let key = keymap
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(combo, _)| {
+                let label = describe_key(combo.code, combo.modifiers);
+                (
+                    combo.modifiers != KeyModifiers::NONE,
+                    label.chars().count(),
+                    label.chars().any(char::is_uppercase),
+                    label,
+                )
+            })
+            .min()?
+            .3;
+        Some(format!("{key} {name}"))
+    })
+    .collect::<Vec<_>>()
+    .join("  ")
+}
+
+fn load_keymap(path: &Path) -> HashMap<KeyCombo, Action> {
+    std::fs::read_to_string(path)
+        .map(|contents| parse_keymap(&contents))
+        .unwrap_or_else(|_| default_keymap())
+}
+
+fn load_theme(path: &Path) -> Option<ThemeKind> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    ThemeKind::parse(setting_value(&contents, "theme")?)
+}
+
+fn setting_value<'a>(contents: &'a str, name: &str) -> Option<&'a str> {
+    contents.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == name).then(|| value.trim().trim_matches('"'))
+    })
+}
+
+fn load_flag(path: &Path, name: &str) -> bool {
+    std::fs::read_to_string(path)
+        .is_ok_and(|contents| setting_value(&contents, name) == Some("true"))
+}
+
+fn load_affix(path: &Path, name: &str) -> Option<String> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    Some(setting_value(&contents, name)?)
+        .filter(|value| is_valid_affix(value))
+        .map(str::to_string)
+}
+
+fn load_strength_min_length(path: &Path) -> Option<usize> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    setting_value(&contents, "strength_min_length")?
+        .parse()
+        .ok()
+        .filter(|min_len| *min_len >= 1)
+}
+
+fn load_thresholds(path: &Path) -> Thresholds {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return Thresholds::default();
+    };
+    let defaults = Thresholds::default();
+    let read = |name: &str, default: f64| {
+        setting_value(&contents, name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    let thresholds = Thresholds {
+        warn: read("gauge_warn_bits", defaults.warn),
+        good: read("gauge_good_bits", defaults.good),
+        full: read("gauge_full_bits", defaults.full),
+    };
+    if thresholds.is_valid() {
+        thresholds
+    } else {
+        defaults
+    }
+}
+
+fn save_theme(path: &Path, theme: ThemeKind) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // keep the other settings, only the theme line is rewritten
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    let mut contents: String = existing
+        .lines()
+        .filter(|line| {
+            line.split_once('=')
+                .is_none_or(|(key, _)| key.trim() != "theme")
+        })
+        .map(|line| format!("{line}\n"))
+        .collect();
+    contents.push_str(&format!("theme = \"{}\"\n", theme.label()));
+    std::fs::write(path, contents)
+}
+
+fn parse_profiles(contents: &str) -> HashMap<String, PasswordSpec> {
+    let mut profiles = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            let name = unquote_name(name.trim());
+            profiles
+                .entry(name.clone())
+                .or_insert_with(PasswordSpec::default);
+            current = Some(name);
+            continue;
+        }
+        let (Some(name), Some((key, value))) = (&current, line.split_once('=')) else {
+            continue;
+        };
+        let Some(spec) = profiles.get_mut(name) else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if let Some(index) = PROFILE_TOGGLES.iter().position(|toggle| *toggle == key) {
+            spec.toggles[index] = value.parse::<bool>().ok().or(spec.toggles[index]);
+            continue;
+        }
+        let Ok(count) = value.parse::<i32>() else {
+            continue;
+        };
+        match key {
+            "letters" => spec.letters = Some(count),
+            "uppercase" => spec.uppercase = Some(count),
+            "symbols" => spec.symbols = Some(count),
+            "numbers" => spec.numbers = Some(count),
+            _ => {}
+        }
+    }
+    profiles
+}
+
+// names are written as quoted strings so `"` and `]` survive the round trip
+fn quote_name(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn unquote_name(name: &str) -> String {
+    let Some(inner) = name
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    else {
+        return name.to_string();
+    };
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => out.extend(chars.next()),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+fn format_profiles(profiles: &HashMap<String, PasswordSpec>) -> String {
+    let mut names: Vec<&String> = profiles.keys().collect();
+    names.sort();
+    let mut out = String::new();
+    for name in names {
+        let spec = &profiles[name];
+        out.push_str(&format!("[{}]\n", quote_name(name)));
+        for (key, value) in [
+            ("letters", spec.letters),
+            ("uppercase", spec.uppercase),
+            ("symbols", spec.symbols),
+            ("numbers", spec.numbers),
+        ] {
+            if let Some(value) = value {
+                out.push_str(&format!("{key} = {value}\n"));
+            }
+        }
+        for (key, value) in PROFILE_TOGGLES.iter().zip(spec.toggles) {
+            if let Some(value) = value {
+                out.push_str(&format!("{key} = {value}\n"));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn load_profiles(path: &Path) -> HashMap<String, PasswordSpec> {
+    std::fs::read_to_string(path)
+        .map(|contents| parse_profiles(&contents))
+        .unwrap_or_default()
+}
+
+fn save_profiles(path: &Path, profiles: &HashMap<String, PasswordSpec>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, format_profiles(profiles))
+}
+
+fn spec_summary(spec: &PasswordSpec) -> String {
+    let show = |count: Option<i32>| count.map_or("-".to_string(), |count| count.to_string());
+    format!(
+        "L{} U{} S{} N{}",
+        show(spec.letters),
+        show(spec.uppercase),
+        show(spec.symbols),
+        show(spec.numbers)
+    )
+}
+
+fn checksum(password: &str) -> String {
+    let mut crc = !0u32;
+    for byte in password.bytes() {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    format!("{:02X}", !crc & 0xFF)
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn spec_args(app: &App) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut push = |flag: &str, value: Option<String>| {
+        args.push(flag.to_string());
+        args.extend(value);
+    };
+    match app.mode {
+        Mode::Random => {}
+        Mode::Pronounceable => push("--pronounceable", None),
+        Mode::Pin => push("--pin", None),
+        Mode::Passphrase => push("--passphrase", None),
+        Mode::Combo => push("--word-combo", None),
+    }
+    match app.mode {
+        Mode::Pin => push("--pin-digits", Some(app.pin_digits.to_string())),
+        Mode::Passphrase => push("--words", Some(app.passphrase_words.to_string())),
+        Mode::Combo => push("--combo-digits", Some(app.combo_digits.to_string())),
+        Mode::Random | Mode::Pronounceable => {}
+    }
+    if app.max_per_field != DEFAULT_MAX_VALUE {
+        push("--max", Some(app.max_per_field.to_string()));
+    }
+    push("--letters", Some(app.letters.to_string()));
+    push("--uppercase", Some(app.uppercase.to_string()));
+    push("--symbols", Some(app.symbols.to_string()));
+    push("--numbers", Some(app.numbers.to_string()));
+    if app.case_ratio {
+        push("--upper-percent", Some(app.upper_pct.to_string()));
+    }
+    if app.symbol_set != chars_of(SYMBOLS) {
+        push("--symbol-set", Some(app.symbol_set.iter().collect()));
+    }
+    if !app.exclude.is_empty() {
+        push("--exclude", Some(app.exclude.iter().collect()));
+    }
+    let confusables = active_confusables(&app.confusables);
+    if !confusables.is_empty() {
+        push("--confusable", Some(confusables.join(",")));
+    }
+    if let Some(cap) = app.max_symbol_run {
+        push("--max-symbol-run", Some(cap.to_string()));
+    }
+    if !app.prefix.is_empty() {
+        push("--prefix", Some(app.prefix.clone()));
+    }
+    if !app.suffix.is_empty() {
+        push("--suffix", Some(app.suffix.clone()));
+    }
+    if !app.layout.is_empty() {
+        push("--layout", Some(format_layout(&app.layout)));
+    }
+    if let Some(pattern) = &app.pattern {
+        push("--match", Some(pattern.as_str().to_string()));
+    }
+    if app.min_strength != Strength::DoNotUse {
+        push(
+            "--min-strength",
+            Some(app.min_strength.label().to_ascii_lowercase()),
+        );
+    }
+    if app.strength_min_length != STRENGTH_MIN_LENGTH {
+        push(
+            "--strength-min-length",
+            Some(app.strength_min_length.to_string()),
+        );
+    }
+    let style = app.passphrase_style;
+    for (on, flag) in [
+        (app.no_ambiguous, "--no-ambiguous"),
+        (app.shell_safe, "--shell-safe"),
+        (app.no_sequences, "--no-sequences"),
+        (app.distinct_only, "--distinct"),
+        (app.leading_letter, "--leading-letter"),
+        (app.no_leading_zero, "--no-leading-zero"),
+        (!app.ascii_printable_only, "--allow-unicode"),
+        (app.allow_space, "--allow-space"),
+        (style.title_case, "--title-case"),
+        (style.capitalize_one, "--capitalize-one"),
+        (style.append_number, "--append-number"),
+        (style.insert_symbol, "--insert-symbol"),
+    ] {
+        if on {
+            push(flag, None);
+        }
+    }
+    args
+}
+
+fn spec_command_line(app: &App) -> String {
+    let mut line = "genpass".to_string();
+    for arg in spec_args(app) {
+        line.push(' ');
+        if arg
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "-_.,".contains(ch))
+        {
+            line.push_str(&arg);
+        } else {
+            line.push_str(&shell_quote(&arg));
+        }
+    }
+    line
+}
+
+fn is_shell_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+fn is_valid_affix(affix: &str) -> bool {
+    !affix.is_empty()
+        && !affix
+            .chars()
+            .any(|ch| ch.is_whitespace() || ch.is_control())
+}
+
+fn export_line(name: &str, password: &str) -> String {
+    format!("export {name}={}", shell_quote(password))
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ClassCounts {
+    lower: usize,
+    upper: usize,
+    symbols: usize,
+    digits: usize,
+    other: usize,
+}
+
+impl std::fmt::Display for ClassCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} lower, {} upper, {} sym, {} digit",
+            self.lower, self.upper, self.symbols, self.digits
+        )?;
+        if self.other > 0 {
+            write!(f, ", {} other", self.other)?;
+        }
+        Ok(())
+    }
+}
+
+fn class_counts(password: &str, symbols: &[char]) -> ClassCounts {
+    let mut counts = ClassCounts::default();
+    for ch in password.chars() {
+        let slot = if ch.is_ascii_lowercase() {
+            &mut counts.lower
+        } else if ch.is_ascii_uppercase() {
+            &mut counts.upper
+        } else if ch.is_ascii_digit() {
+            &mut counts.digits
+        } else if symbols.contains(&ch) {
+            &mut counts.symbols
+        } else {
+            &mut counts.other
+        };
+        *slot += 1;
+    }
+    counts
+}
+
+fn format_metadata(label: &str, password: &str, footer: Option<&str>) -> String {
+    let mut text = if label.is_empty() {
+        password.to_string()
+    } else {
+        format!("Site: {label}\nPassword: {password}")
+    };
+    if let Some(footer) = footer {
+        text.push('\n');
+        text.push_str(footer);
+    }
+    text
+}
+
+fn format_log_line(label: &str, password: &str, mask: bool) -> String {
+    let shown = if mask {
+        "*".repeat(password.chars().count())
+    } else {
+        password.to_string()
+    };
+    if label.is_empty() {
+        shown
+    } else {
+        format!("{label}: {shown}")
+    }
+}
+
+fn load_denylist(path: &Path) -> io::Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_lowercase)
+        .collect())
+}
+
+fn contains_denied(password: &str, denylist: &[String]) -> bool {
+    let password = password.to_lowercase();
+    denylist
+        .iter()
+        .any(|banned| password.contains(&banned.to_lowercase()))
+}
+
+fn is_copy_safe(password: &str) -> bool {
+    password.chars().all(|ch| (' '..='~').contains(&ch))
+}
+
+fn overlap_ratio(a: &str, b: &str) -> f64 {
+    let mut distinct: Vec<char> = a.chars().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.is_empty() {
+        return 0.0;
+    }
+    let shared = distinct.iter().filter(|&&ch| b.contains(ch)).count();
+    shared as f64 / distinct.len() as f64
+}
+
+fn write_secure(path: &Path, contents: &str, overwrite: bool) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let mut file = options.open(path)?;
+        // mode only applies on creation, so tighten a file we are overwriting too
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        file.write_all(contents.as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        let mut file = options.open(path)?;
+        file.write_all(contents.as_bytes())
+    }
+}
+
+fn append_log(path: &Path, line: &str) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        // unmasked lines are plaintext passwords
+        options.mode(0o600);
+        let mut file = options.open(path)?;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        writeln!(file, "{line}")
+    }
+    #[cfg(not(unix))]
+    {
+        let mut file = options.open(path)?;
+        writeln!(file, "{line}")
+    }
+}
+
+fn format_clear_countdown(remaining: Duration) -> String {
+    format!("Clipboard clears in {}s", whole_seconds(remaining))
+}
+
+fn format_paused_countdown(remaining: Duration) -> String {
+    format!(
+        "Clipboard clear paused ({}s left)",
+        whole_seconds(remaining)
+    )
+}
+
+fn whole_seconds(remaining: Duration) -> u64 {
+    remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
+}
+
+fn split_letters(total: i32, upper_pct: i32) -> (i32, i32) {
+    let total = total.max(0);
+    let upper = ((total * upper_pct.clamp(0, 100) + 50) / 100).min(total);
+    (total - upper, upper)
+}
+
+fn distribute(total: i32) -> (i32, i32, i32, i32) {
+    let total = total.max(0);
+    let mut parts = LENGTH_RATIOS.map(|pct| total * pct / 100);
+    // hand the rounding remainder to the largest fractional parts
+    let mut order = [0, 1, 2, 3];
+    order.sort_by_key(|&index| std::cmp::Reverse(total * LENGTH_RATIOS[index] % 100));
+    let remainder = total - parts.iter().sum::<i32>();
+    for &index in order.iter().take(remainder as usize) {
+        parts[index] += 1;
+    }
+    if total >= 4 {
+        for index in 0..parts.len() {
+            if parts[index] == 0 {
+                let largest = (0..parts.len()).max_by_key(|&i| parts[i]).unwrap_or(0);
+                parts[largest] -= 1;
+                parts[index] = 1;
+            }
+        }
+    }
+    (parts[0], parts[1], parts[2], parts[3])
+}
+
+fn should_warn(strength: Strength, warn_on_weak: bool) -> bool {
+    warn_on_weak && strength <= Strength::Weak
+}
+
+fn accel_step(consecutive: u32) -> i32 {
+    match consecutive {
+        0..5 => 1,
+        5..10 => 2,
+        10..20 => 5,
+        _ => 10,
+    }
+}
+
+fn filter_history(hist: &[String], q: &str) -> Vec<usize> {
+    hist.iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.contains(q))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn highlight_spans(text: &str, query: &str, style: Style) -> Vec<Span<'static>> {
+    if query.is_empty() {
+        return vec![Span::raw(text.to_string())];
+    }
+    let mut spans = Vec::new();
+    let mut rest = 0;
+    for (start, matched) in text.match_indices(query) {
+        spans.push(Span::raw(text[rest..start].to_string()));
+        spans.push(Span::styled(matched.to_string(), style));
+        rest = start + matched.len();
+    }
+    spans.push(Span::raw(text[rest..].to_string()));
+    spans
+}
+
+fn next_focus(current: usize, direction: isize) -> usize {
+    let count = (FOCUS_QUIT + 1) as isize;
+    (current as isize + direction).rem_euclid(count) as usize
+}
+
+fn wrapped_lines(len: usize, width: u16) -> u16 {
+    if width == 0 {
+        return 1;
+    }
+    len.div_ceil(width as usize).max(1) as u16
+}
+
+fn max_output_scroll(len: usize, width: u16) -> u16 {
+    // heading and strength lines surround the wrapped password
+    (wrapped_lines(len, width) + 2).saturating_sub(OUTPUT_VISIBLE_LINES)
+}
+
+fn output_inner_width(area_width: u16) -> u16 {
+    // outer margin plus the Output borders
+    area_width.saturating_sub(6)
+}
+
+fn total_length(app: &App) -> i32 {
+    match app.mode {
+        Mode::Random => app.letters + app.uppercase + app.symbols + app.numbers,
+        Mode::Pronounceable => app.letters * 2 + app.symbols + app.numbers,
+        Mode::Pin => app.pin_digits,
+        Mode::Passphrase | Mode::Combo => app.split_affixes(&app.password).1.chars().count() as i32,
+    }
+}
+
+fn total_length_color(total: i32, theme: &Theme) -> Color {
+    if total < 8 {
+        theme.bad
+    } else if total < 12 {
+        theme.warn
+    } else {
+        theme.good
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct GeneratedEntry {
+    index: usize,
+    password: String,
+}
+
+impl GeneratedEntry {
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"index\":{},\"password\":{}}}",
+            self.index,
+            json_string(&self.password)
+        )
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn write_entries(
+    out: &mut impl Write,
+    first: usize,
+    passwords: &mut Vec<String>,
+) -> io::Result<()> {
+    for (offset, password) in passwords.drain(..).enumerate() {
+        let entry = GeneratedEntry {
+            index: first + offset,
+            password,
+        };
+        writeln!(out, "{}", entry.to_json())?;
+    }
+    out.flush()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct BatchRow {
+    password: String,
+    strength: Strength,
+    bits: f64,
+}
+
+fn clamp_selection(selected: usize, len: usize) -> usize {
+    selected.min(len.saturating_sub(1))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CharClass {
+    Letters,
+    Uppercase,
+    Symbols,
+    Numbers,
+}
+
+impl CharClass {
+    fn of(ch: char) -> Self {
+        if ch.is_ascii_lowercase() {
+            CharClass::Letters
+        } else if ch.is_ascii_uppercase() {
+            CharClass::Uppercase
+        } else if ch.is_ascii_digit() {
+            CharClass::Numbers
+        } else {
+            CharClass::Symbols
+        }
+    }
+
+    fn layout_char(self) -> char {
+        match self {
+            CharClass::Letters => 'l',
+            CharClass::Uppercase => 'u',
+            CharClass::Symbols => 's',
+            CharClass::Numbers => 'n',
+        }
+    }
+
+    fn from_function_key(n: u8) -> Option<Self> {
+        match n {
+            1 => Some(CharClass::Letters),
+            2 => Some(CharClass::Uppercase),
+            3 => Some(CharClass::Symbols),
+            4 => Some(CharClass::Numbers),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Preset {
+    Pin,
+    Wifi,
+    Nist,
+    Max,
+}
+
+impl Preset {
+    const ALL: [Preset; 4] = [Preset::Pin, Preset::Wifi, Preset::Nist, Preset::Max];
+
+    fn label(self) -> &'static str {
+        match self {
+            Preset::Pin => "PIN",
+            Preset::Wifi => "WiFi",
+            Preset::Nist => "NIST",
+            Preset::Max => "Max",
+        }
+    }
+
+    fn next(current: Option<Preset>) -> Preset {
+        match current {
+            Some(preset) => {
+                let index = Self::ALL.iter().position(|&p| p == preset).unwrap_or(0);
+                Self::ALL[(index + 1) % Self::ALL.len()]
+            }
+            None => Self::ALL[0],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum RngSource {
+    #[default]
+    Thread,
+    Os,
+}
+
+impl RngSource {
+    fn parse(value: &str) -> Option<RngSource> {
+        match value.to_ascii_lowercase().as_str() {
+            "thread" => Some(RngSource::Thread),
+            "os" => Some(RngSource::Os),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            RngSource::Thread => "thread RNG",
+            RngSource::Os => "OS RNG",
+        }
+    }
+
+    fn rng(self) -> Box<dyn Rng> {
+        match self {
+            RngSource::Thread => Box::new(rng()),
+            RngSource::Os => Box::new(UnwrapErr(SysRng)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Random,
+    Pronounceable,
+    Pin,
+    Passphrase,
+    Combo,
+}
+
+impl Mode {
+    fn label(self) -> &'static str {
+        match self {
+            Mode::Random => "Random",
+            Mode::Pronounceable => "Pronounceable",
+            Mode::Pin => "PIN",
+            Mode::Passphrase => "Passphrase",
+            Mode::Combo => "Word combo",
+        }
+    }
+
+    fn uses_wordlist(self) -> bool {
+        matches!(self, Mode::Passphrase | Mode::Combo)
+    }
+
+    fn next(self) -> Mode {
+        match self {
+            Mode::Random => Mode::Pronounceable,
+            Mode::Pronounceable => Mode::Pin,
+            Mode::Pin => Mode::Passphrase,
+            Mode::Passphrase => Mode::Combo,
+            Mode::Combo => Mode::Random,
+        }
+    }
+}
+
+struct Config {
+    letters: i32,
+    uppercase: i32,
+    symbols: i32,
+    numbers: i32,
+    print: bool,
+    a11y: bool,
+    exports: Vec<String>,
+    copy: bool,
+    quiet: bool,
+    jsonl: bool,
+    check: bool,
+    derive_site: Option<String>,
+    common_check: bool,
+    strength_min_length: Option<usize>,
+    simple: bool,
+    theme: Option<ThemeKind>,
+    thresholds: Thresholds,
+    symbol_set: Vec<char>,
+    max_per_field: i32,
+    pin_digits: i32,
+    passphrase_words: i32,
+    combo_digits: i32,
+    clear_after: Option<Duration>,
+    log_path: Option<PathBuf>,
+    mask_log: bool,
+    no_sequences: bool,
+    distinct_only: bool,
+    leading_letter: bool,
+    no_leading_zero: bool,
+    max_symbol_run: Option<usize>,
+    shell_safe: bool,
+    no_ambiguous: bool,
+    ascii_printable_only: bool,
+    allow_space: bool,
+    min_strength: Strength,
+    upper_pct: Option<i32>,
+    length: Option<i32>,
+    warn_on_weak: bool,
+    single_use: bool,
+    group_size: Option<usize>,
+    copy_grouped: bool,
+    denylist: Vec<String>,
+    avoid: Option<String>,
+    pattern: Option<Regex>,
+    layout: Vec<Option<CharClass>>,
+    exclude: Vec<char>,
+    confusables: [bool; CONFUSABLE_GROUPS.len()],
+    count: usize,
+    out_path: Option<PathBuf>,
+    force: bool,
+    keymap: HashMap<KeyCombo, Action>,
+    dry_run: bool,
+    clipboard_history: bool,
+    meta_breakdown: bool,
+    prefix: Option<String>,
+    suffix: Option<String>,
+    auto_copy: bool,
+    focus_copy_after_generate: bool,
+    rng_source: RngSource,
+    passphrase_style: PassphraseStyle,
+    profiles: HashMap<String, PasswordSpec>,
+    mode: Mode,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            letters: DEFAULT_LETTERS,
+            uppercase: DEFAULT_UPPERCASE,
+            symbols: DEFAULT_SYMBOLS,
+            numbers: DEFAULT_NUMBERS,
+            print: false,
+            a11y: false,
+            exports: Vec::new(),
+            copy: false,
+            quiet: false,
+            jsonl: false,
+            check: false,
+            derive_site: None,
+            common_check: false,
+            strength_min_length: None,
+            simple: false,
+            theme: None,
+            thresholds: Thresholds::default(),
+            symbol_set: chars_of(SYMBOLS),
+            max_per_field: DEFAULT_MAX_VALUE,
+            pin_digits: PIN_MIN_DIGITS,
+            passphrase_words: DEFAULT_PASSPHRASE_WORDS,
+            combo_digits: DEFAULT_COMBO_DIGITS,
+            clear_after: None,
+            log_path: None,
+            mask_log: false,
+            no_sequences: false,
+            distinct_only: false,
+            leading_letter: false,
+            no_leading_zero: false,
+            max_symbol_run: None,
+            shell_safe: false,
+            no_ambiguous: false,
+            ascii_printable_only: true,
+            allow_space: false,
+            min_strength: Strength::DoNotUse,
+            upper_pct: None,
+            length: None,
+            warn_on_weak: false,
+            single_use: false,
+            group_size: None,
+            copy_grouped: false,
+            denylist: Vec::new(),
+            avoid: None,
+            pattern: None,
+            layout: Vec::new(),
+            exclude: Vec::new(),
+            confusables: [false; CONFUSABLE_GROUPS.len()],
+            count: 1,
+            out_path: None,
+            force: false,
+            keymap: default_keymap(),
+            dry_run: false,
+            clipboard_history: false,
+            meta_breakdown: false,
+            prefix: None,
+            suffix: None,
+            auto_copy: false,
+            focus_copy_after_generate: false,
+            rng_source: RngSource::default(),
+            passphrase_style: PassphraseStyle::default(),
+            profiles: HashMap::new(),
+            mode: Mode::Random,
+        }
+    }
+}
+
+/// On/off settings a profile can carry alongside its counts, in `toggles` order.
+const PROFILE_TOGGLES: [&str; 6] = [
+    "no_ambiguous",
+    "no_sequences",
+    "distinct",
+    "leading_letter",
+    "no_leading_zero",
+    "allow_space",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct PasswordSpec {
+    letters: Option<i32>,
+    uppercase: Option<i32>,
+    symbols: Option<i32>,
+    numbers: Option<i32>,
+    toggles: [Option<bool>; PROFILE_TOGGLES.len()],
+}
+
+impl PasswordSpec {
+    /// Overwrites whatever the spec sets; `None` keeps the current value.
+    fn apply(&self, counts: &mut [i32; 4], toggles: [&mut bool; PROFILE_TOGGLES.len()]) {
+        let wanted = [self.letters, self.uppercase, self.symbols, self.numbers];
+        for (count, wanted) in counts.iter_mut().zip(wanted) {
+            *count = wanted.unwrap_or(*count);
+        }
+        for (flag, wanted) in toggles.into_iter().zip(self.toggles) {
+            *flag = wanted.unwrap_or(*flag);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum SpecParseError {
+    MissingValue(String),
+    UnknownKey(String),
+    InvalidValue(String, String),
+}
+
+impl std::fmt::Display for SpecParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpecParseError::MissingValue(entry) => write!(f, "expected key=value, got {entry:?}"),
+            SpecParseError::UnknownKey(key) => {
+                write!(f, "unknown key {key:?} (expected l, u, s or n)")
+            }
+            SpecParseError::InvalidValue(key, value) => {
+                write!(f, "invalid value for {key}: {value:?}")
+            }
+        }
+    }
+}
+
+fn parse_spec_env(s: &str) -> Result<PasswordSpec, SpecParseError> {
+    let mut spec = PasswordSpec::default();
+    for entry in s
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (key, value) = entry
+            .split_once('=')
+            .ok_or_else(|| SpecParseError::MissingValue(entry.to_string()))?;
+        let (key, value) = (key.trim(), value.trim());
+        let count = value
+            .parse::<i32>()
+            .ok()
+            .filter(|count| *count >= 0)
+            .ok_or_else(|| SpecParseError::InvalidValue(key.to_string(), value.to_string()))?;
+        let slot = match key {
+            "l" | "letters" => &mut spec.letters,
+            "u" | "uppercase" => &mut spec.uppercase,
+            "s" | "symbols" => &mut spec.symbols,
+            "n" | "numbers" => &mut spec.numbers,
+            _ => return Err(SpecParseError::UnknownKey(key.to_string())),
+        };
+        *slot = Some(count);
+    }
+    Ok(spec)
+}
+
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let on_off = |flag: bool| if flag { "on" } else { "off" };
+        let or_none = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+        writeln!(f, "mode: {}", self.mode.label())?;
+        writeln!(f, "rng: {}", self.rng_source.label())?;
+        let style = self.passphrase_style;
+        let styles: Vec<&str> = [
+            (style.title_case, "title case"),
+            (style.capitalize_one, "capitalize one"),
+            (style.append_number, "append number"),
+            (style.insert_symbol, "insert symbol"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect();
+        writeln!(
+            f,
+            "passphrase style: {}",
+            if styles.is_empty() {
+                "plain".to_string()
+            } else {
+                styles.join(", ")
+            }
+        )?;
+        writeln!(f, "letters: {}", self.letters)?;
+        writeln!(f, "uppercase: {}", self.uppercase)?;
+        writeln!(f, "symbols: {}", self.symbols)?;
+        writeln!(f, "numbers: {}", self.numbers)?;
+        writeln!(f, "pin digits: {}", self.pin_digits)?;
+        writeln!(f, "passphrase words: {}", self.passphrase_words)?;
+        writeln!(f, "combo digits: {}", self.combo_digits)?;
+        writeln!(
+            f,
+            "length: {}",
+            or_none(self.length.map(|length| length.to_string()))
+        )?;
+        writeln!(
+            f,
+            "upper percent: {}",
+            or_none(self.upper_pct.map(|pct| pct.to_string()))
+        )?;
+        writeln!(f, "max per field: {}", self.max_per_field)?;
+        writeln!(
+            f,
+            "symbol set: {}",
+            self.symbol_set.iter().collect::<String>()
+        )?;
+        writeln!(f, "exclude: {}", self.exclude.iter().collect::<String>())?;
+        writeln!(
+            f,
+            "confusables: {}",
+            active_confusables(&self.confusables).join(" ")
+        )?;
+        writeln!(f, "denylist: {} entries", self.denylist.len())?;
+        writeln!(
+            f,
+            "avoid: {}",
+            if self.avoid.is_some() { "set" } else { "none" }
+        )?;
+        writeln!(
+            f,
+            "match: {}",
+            or_none(self.pattern.as_ref().map(|pattern| pattern.to_string()))
+        )?;
+        writeln!(
+            f,
+            "layout: {}",
+            if self.layout.is_empty() {
+                "none".to_string()
+            } else {
+                format_layout(&self.layout)
+            }
+        )?;
+        writeln!(f, "no sequences: {}", on_off(self.no_sequences))?;
+        writeln!(f, "distinct only: {}", on_off(self.distinct_only))?;
+        writeln!(f, "leading letter: {}", on_off(self.leading_letter))?;
+        writeln!(f, "no leading zero: {}", on_off(self.no_leading_zero))?;
+        writeln!(
+            f,
+            "max symbol run: {}",
+            self.max_symbol_run
+                .map_or("none".to_string(), |cap| cap.to_string())
+        )?;
+        writeln!(f, "shell-safe: {}", on_off(self.shell_safe))?;
+        writeln!(f, "no ambiguous: {}", on_off(self.no_ambiguous))?;
+        writeln!(
+            f,
+            "ascii printable only: {}",
+            on_off(self.ascii_printable_only)
+        )?;
+        writeln!(f, "min strength: {}", self.min_strength.label())?;
+        writeln!(f, "common check: {}", on_off(self.common_check))?;
+        writeln!(f, "derive: {}", or_none(self.derive_site.clone()))?;
+        writeln!(
+            f,
+            "strength min length: {}",
+            self.strength_min_length.unwrap_or(STRENGTH_MIN_LENGTH)
+        )?;
+        writeln!(
+            f,
+            "group: {}",
+            or_none(self.group_size.map(|size| size.to_string()))
+        )?;
+        writeln!(f, "copy grouped: {}", on_off(self.copy_grouped))?;
+        writeln!(f, "single use: {}", on_off(self.single_use))?;
+        writeln!(f, "warn on weak: {}", on_off(self.warn_on_weak))?;
+        writeln!(
+            f,
+            "clear after: {}",
+            or_none(
+                self.clear_after
+                    .map(|after| format!("{}s", after.as_secs()))
+            )
+        )?;
+        writeln!(
+            f,
+            "log: {}",
+            or_none(
+                self.log_path
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+            )
+        )?;
+        writeln!(f, "mask log: {}", on_off(self.mask_log))?;
+        writeln!(
+            f,
+            "theme: {}",
+            self.theme.unwrap_or(ThemeKind::Dark).label()
+        )?;
+        writeln!(f, "count: {}", self.count)?;
+        writeln!(f, "jsonl: {}", on_off(self.jsonl))?;
+        writeln!(
+            f,
+            "out: {}",
+            or_none(
+                self.out_path
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+            )
+        )?;
+        writeln!(f, "clipboard history: {}", on_off(self.clipboard_history))?;
+        writeln!(f, "meta breakdown: {}", on_off(self.meta_breakdown))?;
+        writeln!(f, "prefix: {}", or_none(self.prefix.clone()))?;
+        writeln!(f, "suffix: {}", or_none(self.suffix.clone()))?;
+        writeln!(f, "auto copy: {}", on_off(self.auto_copy))?;
+        writeln!(
+            f,
+            "focus copy after generate: {}",
+            on_off(self.focus_copy_after_generate)
+        )?;
+        writeln!(f, "force: {}", on_off(self.force))
+    }
+}
+
+fn parse_count(flag: &str, value: Option<String>) -> Result<i32, String> {
+    let value = value.ok_or(format!("{flag} requires a value"))?;
+    value
+        .parse::<i32>()
+        .ok()
+        .filter(|count| *count >= 0)
+        .ok_or(format!("invalid value for {flag}: {value}"))
+}
+
+fn parse_bounded(flag: &str, value: Option<String>, min: i32, max: i32) -> Result<i32, String> {
+    let count = parse_count(flag, value)?;
+    if !(min..=max).contains(&count) {
+        return Err(format!(
+            "{flag} must be between {min} and {max}, got {count}"
+        ));
+    }
+    Ok(count)
+}
+
+impl Config {
+    fn from_spec(spec: &PasswordSpec) -> Self {
+        let mut config = Self::default();
+        config.apply_spec(spec);
+        config
+    }
+
+    fn apply_spec(&mut self, spec: &PasswordSpec) {
+        let mut counts = [self.letters, self.uppercase, self.symbols, self.numbers];
+        spec.apply(
+            &mut counts,
+            [
+                &mut self.no_ambiguous,
+                &mut self.no_sequences,
+                &mut self.distinct_only,
+                &mut self.leading_letter,
+                &mut self.no_leading_zero,
+                &mut self.allow_space,
+            ],
+        );
+        [self.letters, self.uppercase, self.symbols, self.numbers] = counts;
+    }
+
+    fn from_env() -> Self {
+        // a broken GENPASS_SPEC should never stop the tool from starting
+        std::env::var("GENPASS_SPEC")
+            .ok()
+            .and_then(|value| parse_spec_env(&value).ok())
+            .map_or_else(Self::default, |spec| Self::from_spec(&spec))
+    }
+
+    #[cfg(test)]
+    fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        Self::from_args_over(Self::default(), args)
+    }
+
+    fn from_args_over(
+        base: Config,
+        args: impl IntoIterator<Item = String>,
+    ) -> Result<Self, String> {
+        let mut config = base;
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--letters" => config.letters = parse_count(&arg, args.next())?,
+                "--uppercase" => config.uppercase = parse_count(&arg, args.next())?,
+                "--symbols" => config.symbols = parse_count(&arg, args.next())?,
+                "--numbers" => config.numbers = parse_count(&arg, args.next())?,
+                "--print" => config.print = true,
+                "--copy" => {
+                    config.copy = true;
+                    config.print = true;
+                }
+                "--quiet" => config.quiet = true,
+                "--jsonl" => {
+                    config.jsonl = true;
+                    config.print = true;
+                }
+                "--a11y" => config.a11y = true,
+                "--export" => {
+                    let name = args.next().ok_or("--export requires a variable name")?;
+                    if !is_shell_name(&name) {
+                        return Err(format!("invalid value for --export: {name}"));
+                    }
+                    config.exports.push(name);
+                    config.print = true;
+                }
+                "--count" => {
+                    let value = args.next().ok_or("--count requires a value")?;
+                    config.count = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|count| *count >= 1)
+                        .ok_or(format!("invalid value for --count: {value}"))?;
+                    // the TUI shows one password, so a count only makes sense printed
+                    config.print = true;
+                }
+                "--out" => {
+                    let value = args.next().ok_or("--out requires a path")?;
+                    config.out_path = Some(PathBuf::from(value));
+                    config.print = true;
+                }
+                "--force" => config.force = true,
+                "--dry-run" => config.dry_run = true,
+                "--clipboard-history" => config.clipboard_history = true,
+                "--meta-breakdown" => config.meta_breakdown = true,
+                "--prefix" | "--suffix" => {
+                    let value = args.next().ok_or(format!("{arg} requires a value"))?;
+                    if !is_valid_affix(&value) {
+                        return Err(format!("invalid value for {arg}: {value}"));
+                    }
+                    if arg == "--prefix" {
+                        config.prefix = Some(value);
+                    } else {
+                        config.suffix = Some(value);
+                    }
+                }
+                "--auto-copy" => config.auto_copy = true,
+                "--check" => config.check = true,
+                "--derive" => {
+                    let site = args.next().ok_or("--derive requires a site label")?;
+                    if site.trim().is_empty() {
+                        return Err("invalid value for --derive: empty site".to_string());
+                    }
+                    config.derive_site = Some(site);
+                }
+                "--common-check" => config.common_check = true,
+                "--strength-min-length" => {
+                    let value = args
+                        .next()
+                        .ok_or("--strength-min-length requires a value")?;
+                    let min_len = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|min_len| *min_len >= 1)
+                        .ok_or(format!("invalid value for --strength-min-length: {value}"))?;
+                    config.strength_min_length = Some(min_len);
+                }
+                "--simple" => config.simple = true,
+                "--profile" => {
+                    let value = args.next().ok_or("--profile requires a name")?;
+                    let spec = config
+                        .profiles
+                        .get(&value)
+                        .cloned()
+                        .ok_or(format!("unknown profile: {value}"))?;
+                    config.apply_spec(&spec);
+                }
+                "--rng" => {
+                    let value = args.next().ok_or("--rng requires a value")?;
+                    config.rng_source = RngSource::parse(&value).ok_or(format!(
+                        "invalid value for --rng: {value} (use os or thread)"
+                    ))?;
+                }
+                "--theme" => {
+                    let value = args.next().ok_or("--theme requires a value")?;
+                    config.theme = Some(
+                        ThemeKind::parse(&value)
+                            .ok_or(format!("invalid value for --theme: {value}"))?,
+                    );
+                }
+                "--symbol-set" => {
+                    let value = args.next().ok_or("--symbol-set requires a value")?;
+                    config.symbol_set =
+                        sanitize_symbol_set(&value).map_err(|err| err.to_string())?;
+                }
+                "--max" => {
+                    let value = args.next().ok_or("--max requires a value")?;
+                    let max = value
+                        .parse::<i32>()
+                        .map_err(|_| format!("invalid value for --max: {value}"))?;
+                    if max < 1 {
+                        return Err(format!("--max must be at least 1, got {max}"));
+                    }
+                    config.max_per_field = max.min(MAX_TOTAL_LENGTH);
+                }
+                "--clear-after" => {
+                    let value = args.next().ok_or("--clear-after requires a value")?;
+                    let seconds = value
+                        .parse::<u64>()
+                        .map_err(|_| format!("invalid value for --clear-after: {value}"))?;
+                    config.clear_after = Some(Duration::from_secs(seconds));
+                }
+                "--log" => {
+                    let value = args.next().ok_or("--log requires a path")?;
+                    config.log_path = Some(PathBuf::from(value));
+                }
+                "--mask-log" => config.mask_log = true,
+                "--no-sequences" => config.no_sequences = true,
+                "--distinct" => config.distinct_only = true,
+                "--leading-letter" => config.leading_letter = true,
+                "--no-leading-zero" => config.no_leading_zero = true,
+                "--max-symbol-run" => {
+                    let value = args.next().ok_or("--max-symbol-run requires a value")?;
+                    let cap = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|cap| *cap >= 1)
+                        .ok_or(format!("invalid value for --max-symbol-run: {value}"))?;
+                    config.max_symbol_run = Some(cap);
+                }
+                "--pronounceable" => config.mode = Mode::Pronounceable,
+                "--pin" => config.mode = Mode::Pin,
+                "--passphrase" => config.mode = Mode::Passphrase,
+                "--word-combo" => config.mode = Mode::Combo,
+                "--pin-digits" => {
+                    config.pin_digits =
+                        parse_bounded(&arg, args.next(), PIN_MIN_DIGITS, PIN_MAX_DIGITS)?;
+                }
+                "--words" => {
+                    config.passphrase_words = parse_bounded(
+                        &arg,
+                        args.next(),
+                        PASSPHRASE_MIN_WORDS,
+                        PASSPHRASE_MAX_WORDS,
+                    )?;
+                }
+                "--combo-digits" => {
+                    config.combo_digits =
+                        parse_bounded(&arg, args.next(), COMBO_MIN_DIGITS, COMBO_MAX_DIGITS)?;
+                }
+                "--title-case" => config.passphrase_style.title_case = true,
+                "--capitalize-one" => config.passphrase_style.capitalize_one = true,
+                "--append-number" => config.passphrase_style.append_number = true,
+                "--insert-symbol" => config.passphrase_style.insert_symbol = true,
+                "--upper-percent" => {
+                    let value = args.next().ok_or("--upper-percent requires a value")?;
+                    let pct = value
+                        .parse::<i32>()
+                        .ok()
+                        .filter(|pct| (0..=100).contains(pct))
+                        .ok_or(format!("invalid value for --upper-percent: {value}"))?;
+                    config.upper_pct = Some(pct);
+                }
+                "--length" => {
+                    let value = args.next().ok_or("--length requires a value")?;
+                    let length = value
+                        .parse::<i32>()
+                        .ok()
+                        .filter(|length| (0..=MAX_TOTAL_LENGTH).contains(length))
+                        .ok_or(format!("invalid value for --length: {value}"))?;
+                    config.length = Some(length);
+                }
+                "--shell-safe" => config.shell_safe = true,
+                "--no-ambiguous" => config.no_ambiguous = true,
+                "--allow-unicode" => config.ascii_printable_only = false,
+                "--allow-space" => config.allow_space = true,
+                "--warn-on-weak" => config.warn_on_weak = true,
+                "--single-use" => config.single_use = true,
+                "--group" => {
+                    let value = args.next().ok_or("--group requires a value")?;
+                    let size = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|size| *size >= 1)
+                        .ok_or(format!("invalid value for --group: {value}"))?;
+                    config.group_size = Some(size);
+                }
+                "--copy-grouped" => config.copy_grouped = true,
+                "--exclude" => {
+                    let value = args.next().ok_or("--exclude requires a value")?;
+                    config.exclude = parse_exclude(&value);
+                }
+                "--confusable" => {
+                    let value = args.next().ok_or("--confusable requires a value")?;
+                    for name in value.split(',') {
+                        let index = CONFUSABLE_GROUPS
+                            .iter()
+                            .position(|group| *group == name)
+                            .ok_or(format!(
+                                "invalid value for --confusable: {name} (use {})",
+                                CONFUSABLE_GROUPS.join(", ")
+                            ))?;
+                        config.confusables[index] = true;
+                    }
+                }
+                "--deny" => {
+                    let value = args.next().ok_or("--deny requires a path")?;
+                    config.denylist = load_denylist(Path::new(&value))
+                        .map_err(|err| format!("could not read denylist {value}: {err}"))?;
+                }
+                "--layout" => {
+                    let value = args.next().ok_or("--layout requires a pattern")?;
+                    config.layout = parse_layout(&value).ok_or(format!(
+                        "invalid value for --layout: {value} (use l, u, s, n or ?)"
+                    ))?;
+                }
+                "--match" => {
+                    let value = args.next().ok_or("--match requires a regex")?;
+                    config.pattern = Some(
+                        Regex::new(&value)
+                            .map_err(|err| format!("invalid value for --match: {err}"))?,
+                    );
+                }
+                "--avoid" => {
+                    config.avoid = Some(args.next().ok_or("--avoid requires a password")?);
+                }
+                "--min-strength" => {
+                    let value = args.next().ok_or("--min-strength requires a value")?;
+                    config.min_strength = Strength::parse(&value)
+                        .ok_or(format!("invalid value for --min-strength: {value}"))?;
+                }
+                _ => return Err(format!("unknown argument: {arg}")),
+            }
+        }
+
+        // one password per exported name
+        if !config.exports.is_empty() {
+            config.count = config.exports.len();
+        }
+        for (flag, affix) in [("--prefix", &config.prefix), ("--suffix", &config.suffix)] {
+            if let Some(affix) = affix
+                && config.ascii_printable_only
+                && !is_copy_safe(affix)
+            {
+                return Err(format!(
+                    "invalid value for {flag}: {affix} is not printable ASCII (pass --allow-unicode)"
+                ));
+            }
+        }
+        if config.jsonl
+            && (config.copy
+                || config.quiet
+                || !config.exports.is_empty()
+                || config.out_path.is_some())
+        {
+            return Err(
+                "--jsonl cannot be combined with --copy, --quiet, --export or --out".to_string(),
+            );
+        }
+        Ok(config)
+    }
+}
+
+struct HoldState {
+    focus: usize,
+    direction: i32,
+    last: Instant,
+    consecutive: u32,
+}
+
+struct App {
+    letters: i32,
+    uppercase: i32,
+    symbols: i32,
+    numbers: i32,
+    prev_counts: [i32; 4],
+    mode_focus: [usize; 5],
+    focus: usize,
+    output_scroll: u16,
+    password: String,
+    previous_password: String,
+    flash_until: Option<Instant>,
+    hold: Option<HoldState>,
+    dirty: bool,
+    warn_on_weak: bool,
+    weak_flash_until: Option<Instant>,
+    bell_pending: bool,
+    copy_count: u32,
+    single_use: bool,
+    group_size: Option<usize>,
+    copy_grouped: bool,
+    denylist: Vec<String>,
+    denied: bool,
+    avoid: Option<String>,
+    too_similar: bool,
+    pattern: Option<Regex>,
+    layout: Vec<Option<CharClass>>,
+    unmatched: bool,
+    unsafe_chars: bool,
+    strength: Strength,
+    min_strength: Strength,
+    status: String,
+    status_until: Option<Instant>,
+    clear_after: Option<Duration>,
+    clipboard_clear_at: Option<Instant>,
+    clear_seconds_shown: Option<u64>,
+    clipboard_clear_paused: Option<Duration>,
+    clipboard_private: bool,
+    auto_copy: bool,
+    auto_copy_pending: bool,
+    focus_copy_after_generate: bool,
+    rng_source: RngSource,
+    key_hints: String,
+    label: String,
+    label_edit: Option<TextInput>,
+    field_edit: Option<String>,
+    symbol_set: Vec<char>,
+    symbol_edit: Option<TextInput>,
+    exclude: Vec<char>,
+    confusables: [bool; CONFUSABLE_GROUPS.len()],
+    exclude_edit: Option<TextInput>,
+    show_qr: bool,
+    show_breakdown: bool,
+    show_phonetic: bool,
+    show_stats: bool,
+    show_key_log: bool,
+    key_log: Vec<String>,
+    type_countdown: Option<TypeCountdown>,
+    generated_total: u32,
+    a11y: bool,
+    announced: [String; 3],
+    announced_generation: u32,
+    copied_total: u32,
+    compact: bool,
+    bits_gauge: bool,
+    theme: ThemeKind,
+    thresholds: Thresholds,
+    compare_slot: Option<String>,
+    compare_pinned_active: bool,
+    batch: Option<Vec<BatchRow>>,
+    batch_size: usize,
+    batch_selected: usize,
+    batch_typed: Option<usize>,
+    history: Vec<String>,
+    history_filter: Option<String>,
+    profiles: HashMap<String, PasswordSpec>,
+    profile_selected: Option<usize>,
+    profile_name: Option<TextInput>,
+    history_selected: usize,
+    log_path: Option<PathBuf>,
+    mask_log: bool,
+    clipboard: CopyFn,
+    max_per_field: i32,
+    no_sequences: bool,
+    distinct_only: bool,
+    leading_letter: bool,
+    no_leading_zero: bool,
+    max_symbol_run: Option<usize>,
+    mode: Mode,
+    pin_digits: i32,
+    passphrase_words: i32,
+    combo_digits: i32,
+    passphrase_style: PassphraseStyle,
+    case_ratio: bool,
+    upper_pct: i32,
+    length_only: bool,
+    no_ambiguous: bool,
+    shell_safe: bool,
+    ascii_printable_only: bool,
+    allow_space: bool,
+    preset: Option<Preset>,
+    wordlist_error: Option<WordlistError>,
+    common: Option<HashSet<&'static str>>,
+    strength_min_length: usize,
+    meta_breakdown: bool,
+    prefix: String,
+    suffix: String,
+}
+
+impl App {
+    fn new(config: &Config) -> Self {
+        let mut app = Self {
+            letters: 0,
+            uppercase: 0,
+            symbols: 0,
+            numbers: 0,
+            prev_counts: [0; 4],
+            mode_focus: [0; 5],
+            focus: 0,
+            output_scroll: 0,
+            password: String::new(),
+            previous_password: String::new(),
+            flash_until: None,
+            hold: None,
+            dirty: true,
+            warn_on_weak: config.warn_on_weak,
+            weak_flash_until: None,
+            bell_pending: false,
+            copy_count: 0,
+            single_use: config.single_use,
+            group_size: config.group_size,
+            copy_grouped: config.copy_grouped,
+            denylist: config.denylist.clone(),
+            denied: false,
+            avoid: config.avoid.clone(),
+            too_similar: false,
+            pattern: config.pattern.clone(),
+            layout: config.layout.clone(),
+            unmatched: false,
+            unsafe_chars: false,
+            strength: Strength::DoNotUse,
+            min_strength: config.min_strength,
+            status: "".to_string(),
+            status_until: None,
+            clear_after: config.clear_after,
+            clipboard_clear_at: None,
+            clear_seconds_shown: None,
+            clipboard_clear_paused: None,
+            clipboard_private: !config.clipboard_history,
+            auto_copy: config.auto_copy,
+            auto_copy_pending: false,
+            focus_copy_after_generate: config.focus_copy_after_generate,
+            rng_source: config.rng_source,
+            key_hints: key_hints(&config.keymap),
+            label: String::new(),
+            label_edit: None,
+            field_edit: None,
+            symbol_set: config.symbol_set.clone(),
+            symbol_edit: None,
+            exclude: config.exclude.clone(),
+            confusables: config.confusables,
+            exclude_edit: None,
+            show_qr: false,
+            show_breakdown: false,
+            show_phonetic: false,
+            show_stats: false,
+            show_key_log: false,
+            key_log: Vec::new(),
+            type_countdown: None,
+            generated_total: 0,
+            a11y: config.a11y,
+            announced: Default::default(),
+            announced_generation: 0,
+            copied_total: 0,
+            compact: false,
+            bits_gauge: false,
+            theme: config.theme.unwrap_or(ThemeKind::Dark),
+            thresholds: config.thresholds,
+            compare_slot: None,
+            compare_pinned_active: false,
+            batch: None,
+            batch_size: BATCH_SIZE,
+            batch_selected: 0,
+            batch_typed: None,
+            history: Vec::new(),
+            history_filter: None,
+            profiles: config.profiles.clone(),
+            profile_selected: None,
+            profile_name: None,
+            history_selected: 0,
+            log_path: config.log_path.clone(),
+            mask_log: config.mask_log,
+            clipboard: SYSTEM_CLIPBOARD,
+            max_per_field: config.max_per_field,
+            no_sequences: config.no_sequences,
+            distinct_only: config.distinct_only,
+            leading_letter: config.leading_letter,
+            no_leading_zero: config.no_leading_zero,
+            max_symbol_run: config.max_symbol_run,
+            mode: config.mode,
+            pin_digits: config.pin_digits,
+            passphrase_words: config.passphrase_words,
+            combo_digits: config.combo_digits,
+            passphrase_style: config.passphrase_style,
+            case_ratio: config.upper_pct.is_some(),
+            upper_pct: config.upper_pct.unwrap_or(0),
+            length_only: config.length.is_some(),
+            no_ambiguous: config.no_ambiguous,
+            shell_safe: config.shell_safe,
+            ascii_printable_only: config.ascii_printable_only,
+            allow_space: config.allow_space,
+            preset: None,
+            wordlist_error: validate_wordlist(&wordlist()).err(),
+            common: config.common_check.then(common_passwords),
+            strength_min_length: config.strength_min_length.unwrap_or(STRENGTH_MIN_LENGTH),
+            meta_breakdown: config.meta_breakdown,
+            prefix: config.prefix.clone().unwrap_or_default(),
+            suffix: config.suffix.clone().unwrap_or_default(),
+        };
+        if app.wordlist_error.is_some() && app.mode.uses_wordlist() {
+            app.mode = Mode::Random;
+        }
+        app.letters = app.clamp_value(CharClass::Letters, config.letters);
+        app.uppercase = app.clamp_value(CharClass::Uppercase, config.uppercase);
+        app.symbols = app.clamp_value(CharClass::Symbols, config.symbols);
+        app.numbers = app.clamp_value(CharClass::Numbers, config.numbers);
+        if app.case_ratio {
+            app.apply_case_ratio(app.letters + app.uppercase);
+        }
+        if let Some(length) = config.length {
+            app.apply_length(length);
+        }
+        app.generate_password();
+        if let Some(err) = &app.wordlist_error {
+            app.status = format!("Passphrase mode disabled: {err}.");
+            app.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        app
+    }
+
+    fn next_mode(&self) -> Mode {
+        let mut next = self.mode.next();
+        while next.uses_wordlist() && self.wordlist_error.is_some() {
+            next = next.next();
+        }
+        next
+    }
+
+    fn spec(&self) -> PasswordSpec {
+        PasswordSpec {
+            letters: Some(self.letters),
+            uppercase: Some(self.uppercase),
+            symbols: Some(self.symbols),
+            numbers: Some(self.numbers),
+            ..PasswordSpec::default()
+        }
+    }
+
+    fn excluded_chars(&self) -> Vec<char> {
+        let mut exclude = self.exclude.clone();
+        if self.no_ambiguous {
+            exclude.extend_from_slice(AMBIGUOUS);
+        }
+        if self.shell_safe {
+            exclude.extend_from_slice(SHELL_UNSAFE);
+        }
+        exclude
+    }
+
+    fn pools(&self) -> Pools {
+        let mut pools = Pools::new(&self.symbol_set, &self.excluded_chars());
+        if self.allow_space && !pools.symbols.contains(&' ') {
+            pools.symbols.push(' ');
+        }
+        let groups = active_confusables(&self.confusables);
+        if !groups.is_empty() {
+            for pool in [
+                &mut pools.lowercase,
+                &mut pools.uppercase,
+                &mut pools.symbols,
+                &mut pools.numbers,
+            ] {
+                *pool = filter_confusables(pool, &groups);
+            }
+        }
+        pools
+    }
+
+    fn run_accelerator(&mut self, action: Action) {
+        match action {
+            Action::Generate => self.regenerate(),
+            Action::Copy => self.copy_password(),
+            _ => {}
+        }
+    }
+
+    fn copy_spec_command(&mut self) {
+        self.status = match (self.clipboard)(&spec_command_line(self), self.clipboard_private) {
+            Ok(()) => "Copied the settings as a command line.".to_string(),
+            Err(err) => err.to_string(),
+        };
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        self.dirty = true;
+    }
+
+    fn announcements(&mut self) -> Vec<String> {
+        let generated = if self.password.is_empty() {
+            "password cleared".to_string()
+        } else {
+            announce_generated(self.password.chars().count(), self.strength)
+        };
+        let current = [announce_focus(self), generated, self.status.clone()];
+        // a regeneration with the same length and strength is still news
+        let regenerated = std::mem::replace(&mut self.announced_generation, self.generated_total)
+            != self.generated_total;
+        let mut lines = Vec::new();
+        for (index, (last, now)) in self.announced.iter_mut().zip(current).enumerate() {
+            if *last != now || (index == 1 && regenerated) {
+                if !now.is_empty() {
+                    lines.push(now.clone());
+                }
+                *last = now;
+            }
+        }
+        lines
+    }
+
+    fn toggle_confusable(&mut self, index: usize) {
+        let Some(enabled) = self.confusables.get_mut(index) else {
+            return;
+        };
+        *enabled = !*enabled;
+        self.status = format!(
+            "{} {}.",
+            if *enabled { "Excluding" } else { "Allowing" },
+            CONFUSABLE_GROUPS[index]
+        );
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        self.preset = None;
+    }
+
+    fn uses_bulk_path(&self) -> bool {
+        // anything that can reject or repair a candidate needs the full path
+        self.mode == Mode::Random
+            && !self.no_sequences
+            && !self.leading_letter
+            && !self.no_leading_zero
+            && self.max_symbol_run.is_none()
+            && self.denylist.is_empty()
+            && self.avoid.is_none()
+            && self.pattern.is_none()
+            && self.layout.is_empty()
+            && !self.distinct_only
+            && (!self.ascii_printable_only
+                || is_copy_safe(&self.symbol_set.iter().collect::<String>()))
+            && self.min_strength == Strength::DoNotUse
+            && self.prefix.is_empty()
+            && self.suffix.is_empty()
+    }
+
+    fn generate_candidate(&self, pools: &Pools, rng: &mut impl Rng) -> String {
+        let password = match self.mode {
+            Mode::Random if self.distinct_only => generate_distinct(
+                [self.letters, self.uppercase, self.symbols, self.numbers],
+                pools,
+                rng,
+            ),
+            Mode::Pin if self.distinct_only => {
+                generate_distinct([0, 0, 0, self.pin_digits], pools, rng)
+            }
+            Mode::Random => generate_from_pools(
+                self.letters,
+                self.uppercase,
+                self.symbols,
+                self.numbers,
+                pools,
+                rng,
+            ),
+            Mode::Pronounceable => generate_pronounceable_password(
+                self.letters,
+                self.uppercase,
+                self.symbols,
+                self.numbers,
+                pools,
+                rng,
+            ),
+            Mode::Pin => generate_pin(self.pin_digits, pools, rng),
+            Mode::Passphrase => apply_passphrase_style(
+                &generate_passphrase(self.passphrase_words, &wordlist(), rng),
+                &self.passphrase_style,
+                &pools.symbols,
+                rng,
+            ),
+            Mode::Combo => generate_word_combo(&wordlist(), pools, self.combo_digits, rng).0,
+        };
+        // swapping characters would break the consonant/vowel pattern or the words
+        if matches!(
+            self.mode,
+            Mode::Pronounceable | Mode::Passphrase | Mode::Combo
+        ) {
+            return password;
+        }
+
+        let mut chars: Vec<char> = password.chars().collect();
+        // lay out first so the repairs below only make swaps the layout still allows
+        let layout: &[Option<CharClass>] = if self.mode == Mode::Random {
+            &self.layout
+        } else {
+            &[]
+        };
+        if !layout.is_empty() {
+            apply_layout(&mut chars, layout, pools, rng);
+        }
+        self.repair(&mut chars, layout, pools, rng);
+        chars.into_iter().collect()
+    }
+
+    fn repair(
+        &self,
+        chars: &mut [char],
+        layout: &[Option<CharClass>],
+        pools: &Pools,
+        rng: &mut impl Rng,
+    ) {
+        let leading_letter = self.leading_letter && self.mode == Mode::Random;
+        for _ in 0..MAX_REPAIR_ROUNDS {
+            if self.no_sequences {
+                remove_sequences(chars, layout, pools, self.distinct_only, rng);
+            }
+            if leading_letter {
+                ensure_leading_letter(chars, layout);
+            }
+            if self.no_leading_zero {
+                avoid_leading_zero(chars, layout, pools, rng);
+            }
+            if let Some(cap) = self.max_symbol_run {
+                break_class_runs(chars, layout, CharClass::Symbols, cap, rng);
+            }
+            // moving a letter to the front can create a new run
+            let runs_ok = self
+                .max_symbol_run
+                .is_none_or(|cap| find_class_run(chars, CharClass::Symbols, cap).is_none());
+            if (!self.no_sequences || find_sequence(chars).is_none()) && runs_ok {
+                break;
+            }
+        }
+    }
+
+    // a candidate passing the earlier checks always wins, whatever its strength
+    fn candidate_score(&self, candidate: &str) -> ([bool; 4], Strength) {
+        // the content checks see what gets copied; strength stays on the random core
+        let full = format!("{}{candidate}{}", self.prefix, self.suffix);
+        (
+            [
+                !self.ascii_printable_only || is_copy_safe(&full),
+                !contains_denied(&full, &self.denylist),
+                self.pattern
+                    .as_ref()
+                    // a pattern that gives up backtracking counts as no match
+                    .is_none_or(|pattern| pattern.is_match(&full).unwrap_or(false)),
+                self.avoid
+                    .as_deref()
+                    .is_none_or(|avoid| overlap_ratio(candidate, avoid) <= MAX_AVOID_OVERLAP),
+            ],
+            strength_for_mode(self, candidate),
+        )
+    }
+
+    /// Draws candidates until one passes every check, keeping the best of
+    /// `MAX_GENERATION_ATTEMPTS` otherwise.
+    fn best_candidate(&self, mut next: impl FnMut() -> String) -> (String, ([bool; 4], Strength)) {
+        let mut best = next();
+        let mut best_score = self.candidate_score(&best);
+
+        for _ in 1..MAX_GENERATION_ATTEMPTS {
+            if best_score.0.iter().all(|&ok| ok) && best_score.1 >= self.min_strength {
+                break;
+            }
+            let candidate = next();
+            let candidate_score = self.candidate_score(&candidate);
+            if candidate_score > best_score {
+                best = candidate;
+                best_score = candidate_score;
+            }
+        }
+        (best, best_score)
+    }
+
+    fn generate_password(&mut self) {
+        let pools = self.pools();
+        let short = self.distinct_shortfall(&pools);
+        if !short.is_empty() {
+            self.status = format!(
+                "Not enough distinct {} for the requested count.",
+                short.join("/")
+            );
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+            self.dirty = true;
+            return;
+        }
+        let mut rng = self.rng_source.rng();
+        let (best, best_score) = self.best_candidate(|| self.generate_candidate(&pools, &mut rng));
+        let best_strength = best_score.1;
+
+        let repeats = self.undersized_pools(&pools);
+        if !repeats.is_empty() {
+            self.status = format!(
+                "Fewer distinct {} than requested; some will repeat.",
+                repeats.join("/")
+            );
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        if self.leading_letter && self.mode == Mode::Random && self.letters + self.uppercase == 0 {
+            self.status = "Leading letter needs at least one letter.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        if let Some(cap) = self.max_symbol_run
+            && find_class_run(&best.chars().collect::<Vec<_>>(), CharClass::Symbols, cap).is_some()
+        {
+            self.status = format!("Too many symbols to keep every run to {cap} or fewer.");
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        if self.no_sequences && find_sequence(&best.chars().collect::<Vec<_>>()).is_some() {
+            self.status = "Could not break up every run like abc or 321.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        if self.no_leading_zero && best.starts_with('0') {
+            self.status = "Every allowed digit is zero; cannot avoid a leading zero.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        if best_strength < self.min_strength {
+            self.status = format!(
+                "Could not reach {} in {MAX_GENERATION_ATTEMPTS} tries.",
+                self.min_strength.label()
+            );
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        let empty = self.empty_categories(&pools);
+        if !empty.is_empty() {
+            self.status = format!("Every {} character is excluded; skipping.", empty.join("/"));
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        self.record_checks(best_score.0);
+        if !self.password.is_empty() {
+            self.remember(self.password.clone());
+            self.previous_password = std::mem::take(&mut self.password);
+            self.flash_until = Some(Instant::now() + FLASH_DURATION);
+        }
+        self.password = format!("{}{best}{}", self.prefix, self.suffix);
+        self.strength = best_strength;
+        self.generated_total += 1;
+        self.copy_count = 0;
+        self.output_scroll = 0;
+        self.dirty = true;
+        if should_warn(best_strength, self.warn_on_weak) {
+            self.weak_flash_until = Some(Instant::now() + FLASH_DURATION);
+            self.bell_pending = true;
+        }
+    }
+
+    /// Reports the checks of `candidate_score` a kept password failed.
+    fn record_checks(&mut self, [safe, allowed, matched, distinct]: [bool; 4]) {
+        if !distinct {
+            self.status = format!(
+                "Could not avoid the previous password in {MAX_GENERATION_ATTEMPTS} tries."
+            );
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        if !matched {
+            self.status =
+                format!("No password matched --match in {MAX_GENERATION_ATTEMPTS} tries.");
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        if !allowed {
+            self.status =
+                format!("Could not avoid denied content in {MAX_GENERATION_ATTEMPTS} tries.");
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        if !safe {
+            self.status = match self.non_ascii_symbols() {
+                // no retry can help when the symbols themselves are the problem
+                Some(symbols) => format!(
+                    "Printable-only is on, but {symbols} is not ASCII; press U or pass --allow-unicode."
+                ),
+                None if !is_copy_safe(&format!("{}{}", self.prefix, self.suffix)) => {
+                    "Printable-only is on, but the prefix or suffix is not ASCII; press U or pass --allow-unicode.".to_string()
+                }
+                None => format!(
+                    "Could not avoid non-printable characters in {MAX_GENERATION_ATTEMPTS} tries."
+                ),
+            };
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        self.denied = !allowed;
+        self.too_similar = !distinct;
+        self.unmatched = !matched;
+        self.unsafe_chars = !safe;
+    }
+
+    fn reroll_focused(&mut self) {
+        const CLASSES: [CharClass; 4] = [
+            CharClass::Letters,
+            CharClass::Uppercase,
+            CharClass::Symbols,
+            CharClass::Numbers,
+        ];
+        let plain_fields =
+            self.mode == Mode::Random && !self.uses_length_only() && !self.uses_case_ratio();
+        let Some(&class) = CLASSES.get(self.focus).filter(|_| plain_fields) else {
+            self.status = "Focus a Random mode character field to re-roll it.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+            self.dirty = true;
+            return;
+        };
+        let pools = self.pools();
+        let (prefix, core, suffix) = self.split_affixes(&self.password);
+        let (prefix, suffix) = (prefix.to_string(), suffix.to_string());
+        let core: Vec<char> = core.chars().collect();
+        if pools.for_class(class).is_empty() || !core.iter().any(|&ch| CharClass::of(ch) == class) {
+            self.status = "Nothing of that class to re-roll.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+            self.dirty = true;
+            return;
+        }
+        let mut rng = self.rng_source.rng();
+        // retry only the class positions, then repair and score like a fresh password
+        let (best, (checks, strength)) = self.best_candidate(|| {
+            let mut chars = core.clone();
+            reroll_class(&mut chars, class, &pools, self.distinct_only, &mut rng);
+            self.repair(&mut chars, &self.layout, &pools, &mut rng);
+            chars.into_iter().collect()
+        });
+        if strength < self.min_strength {
+            self.status = format!(
+                "Could not reach {} in {MAX_GENERATION_ATTEMPTS} tries.",
+                self.min_strength.label()
+            );
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        }
+        self.record_checks(checks);
+        self.remember(self.password.clone());
+        self.previous_password =
+            std::mem::replace(&mut self.password, format!("{prefix}{best}{suffix}"));
+        self.strength = strength;
+        self.flash_until = Some(Instant::now() + FLASH_DURATION);
+        self.generated_total += 1;
+        self.copy_count = 0;
+        self.dirty = true;
+    }
+
+    fn count_of(&self, class: CharClass) -> i32 {
+        match class {
+            CharClass::Letters => self.letters,
+            CharClass::Uppercase => self.uppercase,
+            CharClass::Symbols => self.symbols,
+            CharClass::Numbers => self.numbers,
+        }
+    }
+
+    /// Characters each unit of `class` adds to the password in this mode.
+    fn class_width(&self, class: CharClass) -> i32 {
+        match (self.mode, class) {
+            // a syllable is two letters, and uppercase only recases some of them
+            (Mode::Pronounceable, CharClass::Letters) => 2,
+            (Mode::Pronounceable, CharClass::Uppercase) => 0,
+            _ => 1,
+        }
+    }
+
+    fn clamp_value(&self, class: CharClass, value: i32) -> i32 {
+        let width = self.class_width(class);
+        self.clamp_within(value, width * self.count_of(class), width)
+    }
+
+    /// Clamps a count whose characters, `current` of them now at `width` each,
+    /// must fit beside the rest of the password under MAX_TOTAL_LENGTH.
+    fn clamp_within(&self, value: i32, current: i32, width: i32) -> i32 {
+        let others = total_length(self) - current;
+        let max = match width {
+            0 => self.max_per_field,
+            width => self.max_per_field.min((MAX_TOTAL_LENGTH - others) / width),
+        };
+        value.clamp(MIN_VALUE, max.max(MIN_VALUE))
+    }
+
+    fn apply_preset(&mut self, preset: Preset) {
+        let (letters, uppercase, symbols, numbers, no_ambiguous) = match preset {
+            Preset::Pin => (0, 0, 0, 4, false),
+            Preset::Wifi => (10, 4, 2, 4, true),
+            Preset::Nist => (5, 3, 2, 2, false),
+            Preset::Max => (16, 16, 16, 16, false),
+        };
+        self.letters = letters;
+        self.uppercase = uppercase;
+        self.symbols = symbols;
+        self.numbers = numbers;
+        self.no_ambiguous = no_ambiguous;
+        self.length_only = false;
+        self.mode = Mode::Random;
+        self.preset = Some(preset);
+    }
+
+    fn field_visible(&self, index: usize) -> bool {
+        let single_field = matches!(self.mode, Mode::Pin | Mode::Passphrase | Mode::Combo)
+            || self.uses_length_only();
+        !single_field || index == 0 || index >= FOCUS_FIELDS
+    }
+
+    fn clamp_focus(&self, focus: usize) -> usize {
+        let mut focus = focus.min(FOCUS_QUIT);
+        // the first field is visible in every mode
+        while !self.field_visible(focus) {
+            focus -= 1;
+        }
+        focus
+    }
+
+    fn switch_mode(&mut self, mode: Mode) {
+        self.mode_focus[self.mode as usize] = self.focus;
+        self.mode = mode;
+        self.focus = self.clamp_focus(self.mode_focus[mode as usize]);
+    }
+
+    fn cycle_focus(&mut self, direction: isize) {
+        let mut next = next_focus(self.focus, direction);
+        while !self.field_visible(next) {
+            next = next_focus(next, direction);
+        }
+        self.focus = next;
+    }
+
+    fn move_focus(&mut self, steps: isize) {
+        for _ in 0..steps.unsigned_abs() {
+            let mut next = self.focus;
+            loop {
+                next = if steps < 0 {
+                    match next.checked_sub(1) {
+                        Some(next) => next,
+                        None => return,
+                    }
+                } else if next < FOCUS_QUIT {
+                    next + 1
+                } else {
+                    return;
+                };
+                if self.field_visible(next) {
+                    break;
+                }
+            }
+            self.focus = next;
+        }
+    }
+
+    fn uses_case_ratio(&self) -> bool {
+        self.case_ratio && self.mode == Mode::Random && !self.length_only
+    }
+
+    fn uses_length_only(&self) -> bool {
+        self.length_only && self.mode == Mode::Random
+    }
+
+    fn apply_length(&mut self, total: i32) {
+        let total = total.clamp(MIN_VALUE, MAX_TOTAL_LENGTH);
+        (self.letters, self.uppercase, self.symbols, self.numbers) = distribute(total);
+    }
+
+    fn toggle_length_only(&mut self) {
+        self.length_only = !self.length_only;
+        if self.uses_length_only() {
+            self.apply_length(total_length(self));
+        }
+        if !self.field_visible(self.focus) {
+            self.focus = 0;
+        }
+    }
+
+    fn scroll_output(&mut self, delta: i32, width: u16) {
+        let max = max_output_scroll(self.display_password().chars().count(), width);
+        self.output_scroll = (self.output_scroll as i32 + delta).clamp(0, max as i32) as u16;
+        self.dirty = true;
+    }
+
+    fn resize(&mut self, width: u16) {
+        // a narrower output wraps to more lines, so re-clamp the scroll offset
+        self.scroll_output(0, output_inner_width(width));
+    }
+
+    fn toggle_class(&mut self, class: CharClass) {
+        let index = class as usize;
+        let current = self.count_of(class);
+        let value = if current > 0 {
+            self.prev_counts[index] = current;
+            0
+        } else {
+            self.clamp_value(class, self.prev_counts[index].max(1))
+        };
+        match class {
+            CharClass::Letters => self.letters = value,
+            CharClass::Uppercase => self.uppercase = value,
+            CharClass::Symbols => self.symbols = value,
+            CharClass::Numbers => self.numbers = value,
+        }
+        self.preset = None;
+        self.dirty = true;
+    }
+
+    fn apply_case_ratio(&mut self, total_letters: i32) {
+        (self.letters, self.uppercase) = split_letters(total_letters, self.upper_pct);
+    }
+
+    fn toggle_case_ratio(&mut self) {
+        self.case_ratio = !self.case_ratio;
+        if self.case_ratio {
+            let total = self.letters + self.uppercase;
+            self.upper_pct = if total == 0 {
+                0
+            } else {
+                (self.uppercase * 100 + total / 2) / total
+            };
+        }
+    }
+
+    fn hold_step(&mut self, direction: i32, now: Instant) -> i32 {
+        // keys arriving faster than the window count as the key being held
+        let consecutive = match &self.hold {
+            Some(hold)
+                if hold.focus == self.focus
+                    && hold.direction == direction
+                    && now.saturating_duration_since(hold.last) < HOLD_REPEAT_WINDOW =>
+            {
+                hold.consecutive + 1
+            }
+            _ => 0,
+        };
+        self.hold = Some(HoldState {
+            focus: self.focus,
+            direction,
+            last: now,
+            consecutive,
+        });
+        accel_step(consecutive)
+    }
+
+    fn step_value(&mut self, direction: i32, repeat: u32) {
+        let delta = if repeat > 1 {
+            self.hold = None;
+            repeat as i32
+        } else {
+            self.hold_step(direction, Instant::now())
+        };
+        self.update_value(direction * delta);
+    }
+
+    fn update_value(&mut self, delta: i32) {
+        self.dirty = true;
+        if self.focus < FOCUS_FIELDS {
+            self.preset = None;
+        }
+        if self.mode == Mode::Pin {
+            if self.focus == 0 {
+                self.pin_digits = (self.pin_digits + delta).clamp(PIN_MIN_DIGITS, PIN_MAX_DIGITS);
+            }
+            return;
+        }
+        if self.mode == Mode::Passphrase {
+            if self.focus == 0 {
+                self.passphrase_words = (self.passphrase_words + delta)
+                    .clamp(PASSPHRASE_MIN_WORDS, PASSPHRASE_MAX_WORDS);
+            }
+            return;
+        }
+        if self.mode == Mode::Combo {
+            if self.focus == 0 {
+                self.combo_digits =
+                    (self.combo_digits + delta).clamp(COMBO_MIN_DIGITS, COMBO_MAX_DIGITS);
+            }
+            return;
+        }
+        if self.uses_length_only() {
+            if self.focus == 0 {
+                self.apply_length(total_length(self) + delta);
+            }
+            return;
+        }
+        if self.uses_case_ratio() && self.focus < 2 {
+            let total = self.letters + self.uppercase;
+            if self.focus == 0 {
+                let total = self.clamp_within(total + delta, total, 1);
+                self.apply_case_ratio(total);
+            } else {
+                self.upper_pct = (self.upper_pct + delta).clamp(0, 100);
+                self.apply_case_ratio(total);
+            }
+            return;
+        }
+        match self.focus {
+            0 => self.letters = self.clamp_value(CharClass::Letters, self.letters + delta),
+            1 => {
+                self.uppercase = self.clamp_value(CharClass::Uppercase, self.uppercase + delta);
+            }
+            2 => self.symbols = self.clamp_value(CharClass::Symbols, self.symbols + delta),
+            3 => self.numbers = self.clamp_value(CharClass::Numbers, self.numbers + delta),
+            _ => {}
+        }
+    }
+
+    fn field_value(&self) -> Option<i32> {
+        if self.mode == Mode::Pin {
+            return (self.focus == 0).then_some(self.pin_digits);
+        }
+        if self.mode == Mode::Passphrase {
+            return (self.focus == 0).then_some(self.passphrase_words);
+        }
+        if self.mode == Mode::Combo {
+            return (self.focus == 0).then_some(self.combo_digits);
+        }
+        if self.uses_length_only() {
+            return (self.focus == 0).then_some(total_length(self));
+        }
+        if self.uses_case_ratio() {
+            match self.focus {
+                0 => return Some(self.letters + self.uppercase),
+                1 => return Some(self.upper_pct),
+                _ => {}
+            }
+        }
+        match self.focus {
+            0 => Some(self.letters),
+            1 => Some(self.uppercase),
+            2 => Some(self.symbols),
+            3 => Some(self.numbers),
+            _ => None,
+        }
+    }
+
+    fn begin_field_edit(&mut self) {
+        if self.focus < FOCUS_FIELDS {
+            self.field_edit = Some(String::new());
+        }
+    }
+
+    fn push_field_edit(&mut self, ch: char) -> bool {
+        match &mut self.field_edit {
+            Some(input) if ch.is_ascii_digit() && input.len() < MAX_FIELD_INPUT_LEN => {
+                input.push(ch);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn commit_field_edit(&mut self) {
+        let Some(input) = self.field_edit.take() else {
+            return;
+        };
+        if let (Ok(target), Some(current)) = (input.parse::<i32>(), self.field_value()) {
+            self.update_value(target - current);
+        }
+    }
+
+    fn pin_compare(&mut self) {
+        self.compare_slot = Some(self.password.clone());
+        self.compare_pinned_active = false;
+    }
+
+    fn swap_compare(&mut self) {
+        if self.compare_slot.is_some() {
+            self.compare_pinned_active = !self.compare_pinned_active;
+        }
+    }
+
+    fn clear_compare(&mut self) {
+        if let Some(mut pinned) = self.compare_slot.take() {
+            wipe(&mut pinned);
+        }
+        self.compare_pinned_active = false;
+    }
+
+    fn clear_password(&mut self) {
+        wipe(&mut self.password);
+        wipe(&mut self.previous_password);
+        // anything else that would still show a password goes too
+        self.clear_compare();
+        for mut row in self.batch.take().into_iter().flatten() {
+            wipe(&mut row.password);
+        }
+        self.batch_selected = 0;
+        self.batch_typed = None;
+        for entry in &mut self.history {
+            wipe(entry);
+        }
+        self.history.clear();
+        self.strength = Strength::DoNotUse;
+        self.flash_until = None;
+        self.history_filter = None;
+        self.history_selected = 0;
+        self.output_scroll = 0;
+        self.copy_count = 0;
+        self.status = "Cleared.".to_string();
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        self.dirty = true;
+    }
+
+    fn split_affixes<'a>(&self, password: &'a str) -> (&'a str, &'a str, &'a str) {
+        let (prefix, rest) = match password.strip_prefix(self.prefix.as_str()) {
+            Some(rest) => (&password[..self.prefix.len()], rest),
+            None => ("", password),
+        };
+        let (core, suffix) = match rest.strip_suffix(self.suffix.as_str()) {
+            Some(core) => (core, &rest[core.len()..]),
+            None => (rest, ""),
+        };
+        (prefix, core, suffix)
+    }
+
+    fn affixed_strength(&self, password: &str) -> Strength {
+        strength_for_mode(self, self.split_affixes(password).1)
+    }
+
+    fn with_affixes(&self, password: &str, form: impl Fn(&str) -> String) -> String {
+        let (prefix, core, suffix) = self.split_affixes(password);
+        format!("{prefix}{}{suffix}", form(core))
+    }
+
+    fn active_password(&self) -> &str {
+        match &self.compare_slot {
+            Some(pinned) if self.compare_pinned_active => pinned,
+            _ => &self.password,
+        }
+    }
+
+    fn copy_payload(&self) -> String {
+        // PIN fields take bare digits, so PIN groups stay on screen only
+        if self.copy_grouped && self.mode != Mode::Pin {
+            return self.with_affixes(self.active_password(), |core| self.display_form(core));
+        }
+        if self.allow_space {
+            return self.active_password().to_string();
+        }
+        self.active_password()
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .collect()
+    }
+
+    fn show_spaces(&self, password: &str) -> String {
+        // a middot keeps leading and trailing spaces visible and apart from group gaps
+        if self.allow_space {
+            password.replace(' ', "\u{b7}")
+        } else {
+            password.to_string()
+        }
+    }
+
+    fn non_ascii_symbols(&self) -> Option<String> {
+        let symbols: String = self
+            .symbol_set
+            .iter()
+            .filter(|ch| !is_copy_safe(&ch.to_string()))
+            .collect();
+        (!symbols.is_empty()).then_some(symbols)
+    }
+
+    fn toggle_printable_only(&mut self) {
+        self.ascii_printable_only = !self.ascii_printable_only;
+        self.status = if self.ascii_printable_only {
+            "Printable ASCII only.".to_string()
+        } else {
+            "Unicode symbols allowed.".to_string()
+        };
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+    }
+
+    fn toggle_space(&mut self) {
+        self.allow_space = !self.allow_space;
+        self.status = if self.allow_space {
+            "Spaces allowed as symbols.".to_string()
+        } else {
+            "Spaces not allowed.".to_string()
+        };
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        self.preset = None;
+    }
+
+    fn cycle_group_size(&mut self) {
+        self.group_size = match self.group_size {
+            None => Some(GROUP_SIZES[0]),
+            Some(size) => GROUP_SIZES
+                .iter()
+                .position(|&candidate| candidate == size)
+                .and_then(|index| GROUP_SIZES.get(index + 1).copied()),
+        };
+    }
+
+    fn display_password(&self) -> String {
+        self.with_affixes(&self.password, |core| {
+            self.display_form(&self.show_spaces(core))
+        })
+    }
+
+    fn display_form(&self, password: &str) -> String {
+        match self.mode {
+            Mode::Pin => group(password, PIN_GROUP_SIZE, ' '),
+            Mode::Passphrase | Mode::Combo => password.to_string(),
+            _ => match self.group_size {
+                Some(size) => group(password, size, GROUP_SEPARATOR),
+                None => password.to_string(),
+            },
+        }
+    }
+
+    fn weak_flash_active(&self) -> bool {
+        self.weak_flash_until
+            .is_some_and(|deadline| Instant::now() < deadline)
+    }
+
+    fn password_spans(&self, theme: &Theme) -> Vec<Span<'static>> {
+        let (prefix, core, suffix) = self.split_affixes(&self.password);
+        let fixed = Style::default().fg(theme.inactive);
+        let mut spans = vec![Span::styled(prefix.to_string(), fixed)];
+        spans.extend(self.core_spans(core, theme));
+        spans.push(Span::styled(suffix.to_string(), fixed));
+        spans.retain(|span| !span.content.is_empty());
+        spans
+    }
+
+    fn core_spans(&self, core: &str, theme: &Theme) -> Vec<Span<'static>> {
+        let display = self.display_form(&self.show_spaces(core));
+        let flashing = self
+            .flash_until
+            .is_some_and(|deadline| Instant::now() < deadline);
+        if !flashing {
+            return vec![Span::raw(display)];
+        }
+
+        let previous = self.split_affixes(&self.previous_password).1;
+        let mask = diff_mask(&self.display_form(&self.show_spaces(previous)), &display);
+        display
+            .chars()
+            .zip(mask)
+            .map(|(ch, changed)| {
+                if changed {
+                    Span::styled(
+                        ch.to_string(),
+                        Style::default()
+                            .fg(theme.flash)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    Span::raw(ch.to_string())
+                }
+            })
+            .collect()
+    }
+
+    fn paste_into(&mut self, field: TextField) {
+        let text = match paste_from_clipboard() {
+            Ok(text) => sanitize_paste(field, &text),
+            Err(err) => {
+                self.status = err.to_string();
+                self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+                return;
+            }
+        };
+        match field {
+            TextField::Count => {
+                for ch in text.chars() {
+                    self.push_field_edit(ch);
+                }
+            }
+            TextField::Symbols => {
+                if let Some(input) = &mut self.symbol_edit {
+                    input.insert_str(&text);
+                }
+            }
+            TextField::Exclude => {
+                if let Some(input) = &mut self.exclude_edit {
+                    input.insert_str(&text);
+                }
+            }
+            TextField::Label => {
+                if let Some(input) = &mut self.label_edit {
+                    input.insert_str(&text);
+                }
+            }
+        }
+    }
+
+    fn distinct_shortfall(&self, pools: &Pools) -> Vec<&'static str> {
+        if !self.distinct_only {
+            return Vec::new();
+        }
+        self.undersized_pools(pools)
+    }
+
+    fn undersized_pools(&self, pools: &Pools) -> Vec<&'static str> {
+        let categories = match self.mode {
+            Mode::Random => vec![
+                (self.letters, &pools.lowercase, "letters"),
+                (self.uppercase, &pools.uppercase, "uppercase"),
+                (self.symbols, &pools.symbols, "symbols"),
+                (self.numbers, &pools.numbers, "numbers"),
+            ],
+            Mode::Pin => vec![(self.pin_digits, &pools.numbers, "digits")],
+            Mode::Pronounceable | Mode::Passphrase | Mode::Combo => Vec::new(),
+        };
+        categories
+            .into_iter()
+            .filter(|(count, pool, _)| !pool_sufficient(*count, pool.len()))
+            .map(|(_, _, name)| name)
+            .collect()
+    }
+
+    fn empty_categories(&self, pools: &Pools) -> Vec<&'static str> {
+        let categories = match self.mode {
+            Mode::Random => vec![
+                (self.letters, &pools.lowercase, "letters"),
+                (self.uppercase, &pools.uppercase, "uppercase"),
+                (self.symbols, &pools.symbols, "symbols"),
+                (self.numbers, &pools.numbers, "numbers"),
+            ],
+            Mode::Pronounceable => vec![
+                (self.symbols, &pools.symbols, "symbols"),
+                (self.numbers, &pools.numbers, "numbers"),
+            ],
+            Mode::Pin => vec![(self.pin_digits, &pools.numbers, "digits")],
+            Mode::Passphrase => Vec::new(),
+            Mode::Combo => vec![
+                (1, &pools.symbols, "symbols"),
+                (self.combo_digits, &pools.numbers, "digits"),
+            ],
+        };
+        categories
+            .into_iter()
+            .filter(|(count, pool, _)| *count > 0 && pool.is_empty())
+            .map(|(_, _, name)| name)
+            .collect()
+    }
+
+    fn active_text_edit(&self) -> Option<(&'static str, &TextInput)> {
+        [
+            ("Symbol set", &self.symbol_edit),
+            ("Exclude", &self.exclude_edit),
+            ("Site", &self.label_edit),
+        ]
+        .into_iter()
+        .find_map(|(label, edit)| Some((label, edit.as_ref()?)))
+    }
+
+    // both start from the current value so a small change is a small edit
+    fn open_symbol_edit(&mut self) {
+        let current: String = self.symbol_set.iter().collect();
+        self.symbol_edit = Some(TextInput::new(&current));
+    }
+
+    fn open_exclude_edit(&mut self) {
+        let current: String = self.exclude.iter().collect();
+        self.exclude_edit = Some(TextInput::new(&current));
+    }
+
+    fn commit_exclude_edit(&mut self) {
+        if let Some(input) = self.exclude_edit.take() {
+            self.exclude = parse_exclude(&input.text);
+        }
+    }
+
+    fn commit_symbol_edit(&mut self) {
+        let Some(input) = self.symbol_edit.take() else {
+            return;
+        };
+        match sanitize_symbol_set(&input.text) {
+            Ok(symbols) => {
+                self.symbol_set = symbols;
+                if self.ascii_printable_only
+                    && let Some(symbols) = self.non_ascii_symbols()
+                {
+                    self.status = format!(
+                        "{symbols} is not printable ASCII; press U to allow Unicode symbols."
+                    );
+                    self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+                }
+            }
+            Err(err) => {
+                self.status = err.to_string();
+                self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+            }
+        }
+    }
+
+    fn remember(&mut self, password: String) {
+        self.history.retain(|entry| *entry != password);
+        self.history.insert(0, password);
+        self.history.truncate(HISTORY_CAP);
+    }
+
+    fn history_matches(&self) -> Vec<usize> {
+        filter_history(&self.history, self.history_filter.as_deref().unwrap_or(""))
+    }
+
+    fn move_history_selection(&mut self, direction: isize) {
+        let count = self.history_matches().len();
+        if count > 0 {
+            self.history_selected = self
+                .history_selected
+                .saturating_add_signed(direction)
+                .min(count - 1);
+        }
+    }
+
+    fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn open_profiles(&mut self) {
+        if self.profiles.is_empty() {
+            self.status = "No profiles yet. Press O to save one.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        } else {
+            self.profile_selected = Some(0);
+        }
+    }
+
+    fn move_profile_selection(&mut self, direction: isize) {
+        if let Some(selected) = &mut self.profile_selected {
+            *selected = clamp_selection(
+                selected.saturating_add_signed(direction),
+                self.profiles.len(),
+            );
+        }
+    }
+
+    fn apply_spec(&mut self, spec: &PasswordSpec) {
+        let mut counts = [self.letters, self.uppercase, self.symbols, self.numbers];
+        spec.apply(&mut counts, self.toggle_fields());
+        // a hand-edited profile can ask for more than --max or the total cap allow
+        let [letters, uppercase, symbols, numbers] = counts;
+        self.letters = self.clamp_value(CharClass::Letters, letters);
+        self.uppercase = self.clamp_value(CharClass::Uppercase, uppercase);
+        self.symbols = self.clamp_value(CharClass::Symbols, symbols);
+        self.numbers = self.clamp_value(CharClass::Numbers, numbers);
+        self.preset = None;
+        self.dirty = true;
+    }
+
+    fn profile(&self) -> PasswordSpec {
+        PasswordSpec {
+            toggles: [
+                self.no_ambiguous,
+                self.no_sequences,
+                self.distinct_only,
+                self.leading_letter,
+                self.no_leading_zero,
+                self.allow_space,
+            ]
+            .map(Some),
+            ..self.spec()
+        }
+    }
+
+    fn toggle_fields(&mut self) -> [&mut bool; PROFILE_TOGGLES.len()] {
+        [
+            &mut self.no_ambiguous,
+            &mut self.no_sequences,
+            &mut self.distinct_only,
+            &mut self.leading_letter,
+            &mut self.no_leading_zero,
+            &mut self.allow_space,
+        ]
+    }
+
+    fn load_selected_profile(&mut self) {
+        let Some(selected) = self.profile_selected.take() else {
+            return;
+        };
+        let Some(name) = self.profile_names().into_iter().nth(selected) else {
+            return;
+        };
+        let spec = self.profiles[&name].clone();
+        self.apply_spec(&spec);
+        self.generate_password();
+        self.status = format!("Loaded profile {name}");
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+    }
+
+    fn save_profile(&mut self, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        self.profiles.insert(name.to_string(), self.profile());
+        let saved = profiles_path().map(|path| save_profiles(&path, &self.profiles));
+        self.status = match saved {
+            Some(Ok(())) => format!("Saved profile {name}"),
+            Some(Err(err)) => format!("Could not save profiles: {err}"),
+            None => "No config directory for profiles.".to_string(),
+        };
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+    }
+
+    fn select_history(&mut self) {
+        let matches = self.history_matches();
+        self.history_filter = None;
+        let Some(&index) = matches.get(self.history_selected) else {
+            return;
+        };
+        let entry = self.history[index].clone();
+        let current = std::mem::replace(&mut self.password, entry);
+        self.remember(current);
+        self.strength = self.affixed_strength(&self.password);
+        self.copy_count = 0;
+    }
+
+    fn batch_row(&self, core: &str) -> BatchRow {
+        let password = format!("{}{core}{}", self.prefix, self.suffix);
+        BatchRow {
+            strength: self.affixed_strength(&password),
+            bits: entropy_for(self, core),
+            password,
+        }
+    }
+
+    fn batch_rows(&self, count: usize) -> Vec<BatchRow> {
+        let pools = self.pools();
+        let mut rng = self.rng_source.rng();
+        // each row gets the same retries as a single password
+        (0..count)
+            .map(|_| {
+                let (core, _) = self.best_candidate(|| self.generate_candidate(&pools, &mut rng));
+                self.batch_row(&core)
+            })
+            .collect()
+    }
+
+    fn toggle_batch(&mut self) {
+        self.batch_selected = 0;
+        if self.batch.take().is_some() {
+            return;
+        }
+        self.batch = Some(self.batch_rows(self.batch_size));
+    }
+
+    fn regenerate_batch(&mut self) {
+        if self.batch.is_some() {
+            self.batch = Some(self.batch_rows(self.batch_size));
+        }
+    }
+
+    fn resize_batch(&mut self, delta: isize) {
+        self.batch_size = self
+            .batch_size
+            .saturating_add_signed(delta)
+            .clamp(1, MAX_BATCH_SIZE);
+        let extra = self
+            .batch
+            .as_ref()
+            .map_or(0, |rows| self.batch_size.saturating_sub(rows.len()));
+        let more = self.batch_rows(extra);
+        if let Some(rows) = &mut self.batch {
+            rows.truncate(self.batch_size);
+            rows.extend(more);
+            self.batch_selected = clamp_selection(self.batch_selected, rows.len());
+        }
+    }
+
+    fn move_batch_selection(&mut self, direction: isize) {
+        if let Some(rows) = &self.batch {
+            self.batch_selected = clamp_selection(
+                self.batch_selected.saturating_add_signed(direction),
+                rows.len(),
+            );
+        }
+    }
+
+    fn type_batch_digit(&mut self, digit: usize) {
+        let Some(len) = self.batch.as_ref().map(Vec::len) else {
+            return;
+        };
+        let row = match self.batch_typed.take() {
+            Some(typed) if typed * 10 + digit <= len => typed * 10 + digit,
+            _ => digit,
+        };
+        if row == 0 || row > len {
+            return;
+        }
+        // "1" might still become "12", so wait for another digit or Enter
+        if row * 10 <= len {
+            self.batch_selected = row - 1;
+            self.batch_typed = Some(row);
+        } else {
+            self.copy_batch_row(row - 1);
+        }
+    }
+
+    fn copy_batch_row(&mut self, index: usize) {
+        let Some(row) = self.batch.as_ref().and_then(|rows| rows.get(index)) else {
+            return;
+        };
+        let entry = row.password.clone();
+        self.batch_selected = index;
+        if self.copy_text(&entry, &entry) {
+            self.status = format!("Copied #{}", index + 1);
+        }
+    }
+
+    fn copy_blocked(&self) -> bool {
+        self.single_use && self.copy_count > 0
+    }
+
+    fn record_copy(&mut self) {
+        self.copy_count += 1;
+        if self.copy_count > 1 {
+            self.status = format!("Copied (x{})", self.copy_count);
+        }
+    }
+
+    fn copy_tracked(&mut self, text: &str, logged: &str) {
+        if self.copy_blocked() {
+            self.status = "Password already used. Regenerate before copying again.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+            return;
+        }
+        if self.copy_text(text, logged) {
+            self.record_copy();
+        }
+    }
+
+    fn copy_password(&mut self) {
+        let payload = self.copy_payload();
+        if payload.is_empty() {
+            self.status = "Nothing to copy.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+            return;
+        }
+        self.copy_tracked(&payload, &payload);
+    }
+
+    fn copy_with_metadata(&mut self) {
+        // the same password plain copy would take, pinned or current
+        let password = self.active_password().to_string();
+        // counted from the password itself, which filters and repairs can move away from the spec
+        let footer = self.meta_breakdown.then(|| {
+            format!(
+                "Entropy: {:.0} bits\nClasses: {}",
+                estimate_entropy(self),
+                class_counts(&password, &self.pools().symbols)
+            )
+        });
+        let text = format_metadata(&self.label, &password, footer.as_deref());
+        self.copy_tracked(&text, &password);
+    }
+
+    /// Puts `text` on the clipboard, logging only `logged`, the password
+    /// inside it.
+    fn copy_text(&mut self, text: &str, logged: &str) -> bool {
+        self.dirty = true;
+        let copied = (self.clipboard)(text, self.clipboard_private);
+        match &copied {
+            Ok(()) => {
+                self.copied_total += 1;
+                self.status = "Copied to clipboard.".to_string();
+                self.clipboard_clear_at = self.clear_after.map(|after| Instant::now() + after);
+                self.clipboard_clear_paused = None;
+                if let Some(path) = &self.log_path {
+                    let line = format_log_line(&self.label, logged, self.mask_log);
+                    if append_log(path, &line).is_err() {
+                        self.status = "Copied, but writing the log failed.".to_string();
+                    }
+                }
+            }
+            Err(err) => self.status = err.to_string(),
+        }
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+        copied.is_ok()
+    }
+
+    fn clear_clipboard_if_due(&mut self) {
+        if let Some(deadline) = self.clipboard_clear_at
+            && Instant::now() >= deadline
+        {
+            let _ = (self.clipboard)("", self.clipboard_private);
+            self.clipboard_clear_at = None;
+            self.dirty = true;
+        }
+    }
+
+    fn pause_clipboard_clear(&mut self) {
+        if let Some(deadline) = self.clipboard_clear_at.take() {
+            self.clipboard_clear_paused = Some(deadline.saturating_duration_since(Instant::now()));
+            self.dirty = true;
+        }
+    }
+
+    fn resume_clipboard_clear(&mut self) {
+        if let Some(remaining) = self.clipboard_clear_paused.take() {
+            self.clipboard_clear_at = Some(Instant::now() + remaining);
+            self.dirty = true;
+        }
+    }
+
+    fn toggle_clipboard_clear_pause(&mut self) {
+        if self.clipboard_clear_paused.is_some() {
+            self.resume_clipboard_clear();
+        } else if self.clipboard_clear_at.is_some() {
+            self.pause_clipboard_clear();
+        } else {
+            self.status = "No clipboard clear is pending.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+            self.dirty = true;
+        }
+    }
+
+    fn status_line(&self) -> String {
+        let countdown = match (self.clipboard_clear_at, self.clipboard_clear_paused) {
+            (Some(deadline), _) => Some(format_clear_countdown(
+                deadline.saturating_duration_since(Instant::now()),
+            )),
+            (None, Some(remaining)) => Some(format_paused_countdown(remaining)),
+            (None, None) => None,
+        };
+
+        match (self.status.is_empty(), countdown) {
+            (true, None) => String::new(),
+            (false, None) => self.status.clone(),
+            (true, Some(countdown)) => countdown,
+            (false, Some(countdown)) => format!("{}  {countdown}", self.status),
+        }
+    }
+
+    fn clear_status_if_expired(&mut self) {
+        if let Some(deadline) = self.status_until
+            && Instant::now() >= deadline
+        {
+            self.status.clear();
+            self.status_until = None;
+            self.dirty = true;
+        }
+    }
+
+    fn regenerate(&mut self) {
+        self.generate_password();
+        self.after_generate();
+    }
+
+    fn after_generate(&mut self) {
+        // the copy waits for tick so a burst of regenerations copies only the last one
+        self.auto_copy_pending = self.auto_copy;
+        if self.focus_copy_after_generate && self.focus != FOCUS_COPY {
+            self.focus = FOCUS_COPY;
+            self.dirty = true;
+        }
+    }
+
+    fn tick(&mut self) {
+        if std::mem::take(&mut self.auto_copy_pending) {
+            self.copy_password();
+        }
+        self.clear_status_if_expired();
+        self.clear_clipboard_if_due();
+        let now = Instant::now();
+        for deadline in [&mut self.flash_until, &mut self.weak_flash_until] {
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                *deadline = None;
+                self.dirty = true;
+            }
+        }
+        // the countdown in the status line only changes once a second
+        let seconds = self
+            .clipboard_clear_at
+            .map(|deadline| whole_seconds(deadline.saturating_duration_since(now)));
+        if seconds != self.clear_seconds_shown {
+            self.clear_seconds_shown = seconds;
+            self.dirty = true;
+        }
+        self.advance_type_countdown(now);
+    }
+
+    fn handle_unhandled_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+        keymap: &HashMap<KeyCombo, Action>,
+        repeat: u32,
+    ) {
+        let key = describe_key(code, modifiers);
+        let action = relaxed_motion(keymap, code, modifiers);
+        self.key_log.insert(
+            0,
+            match action {
+                Some(action) => format!("{key} -> {action:?}"),
+                None => format!("{key} unhandled"),
+            },
+        );
+        self.key_log.truncate(KEY_LOG_CAP);
+        match action {
+            Some(Action::FocusUp) => self.move_focus(-(repeat as isize)),
+            Some(Action::FocusDown) => self.move_focus(repeat as isize),
+            Some(Action::Dec) => self.step_value(-1, repeat),
+            Some(Action::Inc) => self.step_value(1, repeat),
+            _ => {}
+        }
+        let unusual = !(modifiers - KeyModifiers::SHIFT).is_empty()
+            || matches!(code, KeyCode::Char(ch) if ch.is_ascii_uppercase());
+        if action.is_some() {
+            self.status = format!("Read {key} as a plain key. Check Caps Lock or held modifiers.");
+        } else if unusual {
+            self.status = format!("No binding for {key}. Press F12 for the key log.");
+        } else {
+            return;
+        }
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+    }
+
+    fn start_type_countdown(&mut self) {
+        if !cfg!(feature = "autotype") {
+            self.status = "Typing needs a build with --features autotype.".to_string();
+            self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+            return;
+        }
+        let now = Instant::now();
+        self.type_countdown = Some(TypeCountdown::new(now));
+        self.status = countdown_message(TYPE_COUNTDOWN_SECS);
+        self.status_until = None;
+        self.dirty = true;
+    }
+
+    fn cancel_type_countdown(&mut self) {
+        self.type_countdown = None;
+        self.status = "Typing cancelled.".to_string();
+        self.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+    }
+
+    fn advance_type_countdown(&mut self, now: Instant) {
+        let Some(countdown) = &mut self.type_countdown else {
+            return;
+        };
+        match countdown.advance(now) {
+            CountdownStep::Waiting => return,
+            CountdownStep::Tick(remaining) => self.status = countdown_message(remaining),
+            CountdownStep::Fire => {
+                self.type_countdown = None;
+                self.status = match type_password(&self.copy_payload()) {
+                    Ok(()) => "Typed the password.".to_string(),
+                    Err(err) => format!("Could not type the password: {err}"),
+                };
+                self.status_until = Some(now + CLIPBOARD_MESSAGE_DURATION);
+            }
+        }
+        self.dirty = true;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct TypeCountdown {
+    remaining: u32,
+    next_at: Instant,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum CountdownStep {
+    Waiting,
+    Tick(u32),
+    Fire,
+}
+
+impl TypeCountdown {
+    fn new(now: Instant) -> Self {
+        Self {
+            remaining: TYPE_COUNTDOWN_SECS,
+            next_at: now + Duration::from_secs(1),
+        }
+    }
+
+    fn advance(&mut self, now: Instant) -> CountdownStep {
+        if now < self.next_at {
+            return CountdownStep::Waiting;
+        }
+        if self.remaining <= 1 {
+            return CountdownStep::Fire;
+        }
+        self.remaining -= 1;
+        self.next_at += Duration::from_secs(1);
+        CountdownStep::Tick(self.remaining)
+    }
+}
+
+fn countdown_message(remaining: u32) -> String {
+    format!("Typing in {remaining}... focus the target field (Esc cancels)")
+}
+
+#[cfg(feature = "autotype")]
+fn type_password(password: &str) -> Result<(), String> {
+    use enigo::{Enigo, Keyboard, Settings};
+    let mut enigo = Enigo::new(&Settings::default()).map_err(|err| err.to_string())?;
+    enigo.text(password).map_err(|err| err.to_string())
+}
+
+#[cfg(not(feature = "autotype"))]
+fn type_password(_: &str) -> Result<(), String> {
+    Err("Built without the autotype feature.".to_string())
+}
+
+#[derive(Debug)]
+enum ClipboardError {
+    NoBackend,
+    SpawnFailed(io::Error),
+    WriteFailed,
+    ReadFailed,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    TimedOut,
+    #[cfg_attr(any(unix, windows), allow(dead_code))]
+    Unsupported,
+}
+
+impl std::fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardError::NoBackend => write!(
+                f,
+                "No clipboard tool found. Install {}.",
+                copy_command(cfg!(target_os = "macos"))
+            ),
+            ClipboardError::SpawnFailed(err) => write!(f, "Could not start clipboard tool: {err}"),
+            ClipboardError::WriteFailed => write!(f, "Clipboard tool rejected the password."),
+            ClipboardError::ReadFailed => write!(f, "Could not read text from the clipboard."),
+            ClipboardError::TimedOut => write!(f, "Clipboard tool did not finish in time."),
+            ClipboardError::Unsupported => {
+                write!(f, "Clipboard is not supported on this platform.")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+enum ClipboardExclusion {
+    Monitoring,
+    History,
+    Cloud,
+}
+
+impl ClipboardExclusion {
+    #[cfg(test)]
+    fn format_name(self) -> &'static str {
+        match self {
+            ClipboardExclusion::Monitoring => "ExcludeClipboardContentFromMonitorProcessing",
+            ClipboardExclusion::History => "CanIncludeInClipboardHistory",
+            ClipboardExclusion::Cloud => "CanUploadToCloudClipboard",
+        }
+    }
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn clipboard_exclusions(private: bool) -> &'static [ClipboardExclusion] {
+    if private {
+        &[
+            ClipboardExclusion::Monitoring,
+            ClipboardExclusion::History,
+            ClipboardExclusion::Cloud,
+        ]
+    } else {
+        &[]
+    }
+}
+
+fn copy_command(macos: bool) -> &'static str {
+    if macos { "pbcopy" } else { "wl-copy" }
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+fn paste_command(macos: bool) -> (&'static str, &'static [&'static str]) {
+    if macos {
+        ("pbpaste", &[])
+    } else {
+        ("wl-paste", &["--no-newline"])
+    }
+}
+
+/// Writes a value to the clipboard; the `bool` asks to keep it out of clipboard history.
+type CopyFn = fn(&str, bool) -> Result<(), ClipboardError>;
+
+// tests record copies instead of clobbering the clipboard of whoever runs them
+#[cfg(not(test))]
+const SYSTEM_CLIPBOARD: CopyFn = copy_to_clipboard;
+#[cfg(test)]
+const SYSTEM_CLIPBOARD: CopyFn = tests::record_copy;
+
+#[cfg(unix)]
+fn copy_to_clipboard(value: &str, _private: bool) -> Result<(), ClipboardError> {
+    let mut child = Command::new(copy_command(cfg!(target_os = "macos")))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => ClipboardError::NoBackend,
+            _ => ClipboardError::SpawnFailed(err),
+        })?;
+
+    let mut stdin = child.stdin.take().ok_or(ClipboardError::WriteFailed)?;
+    stdin
+        .write_all(value.as_bytes())
+        .map_err(|_| ClipboardError::WriteFailed)?;
+    drop(stdin);
+
+    match wait_with_timeout(&mut child, CLIPBOARD_TIMEOUT) {
+        Ok(Some(status)) if status.success() => Ok(()),
+        Ok(None) => Err(ClipboardError::TimedOut),
+        _ => Err(ClipboardError::WriteFailed),
+    }
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+fn wait_with_timeout(
+    child: &mut std::process::Child,
+    timeout: Duration,
+) -> io::Result<Option<std::process::ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[cfg(unix)]
+fn paste_from_clipboard() -> Result<String, ClipboardError> {
+    let (program, args) = paste_command(cfg!(target_os = "macos"));
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => ClipboardError::NoBackend,
+            _ => ClipboardError::SpawnFailed(err),
+        })?;
+    if !output.status.success() {
+        return Err(ClipboardError::ReadFailed);
+    }
+    String::from_utf8(output.stdout).map_err(|_| ClipboardError::ReadFailed)
+}
+
+#[cfg(windows)]
+fn paste_from_clipboard() -> Result<String, ClipboardError> {
+    let mut clipboard = Clipboard::new().map_err(|_| ClipboardError::NoBackend)?;
+
+    clipboard.get_text().map_err(|_| ClipboardError::ReadFailed)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn paste_from_clipboard() -> Result<String, ClipboardError> {
+    Err(ClipboardError::Unsupported)
+}
+
+#[cfg(windows)]
+fn copy_to_clipboard(value: &str, private: bool) -> Result<(), ClipboardError> {
+    let mut clipboard = Clipboard::new().map_err(|_| ClipboardError::NoBackend)?;
+
+    let mut set = clipboard.set();
+    for exclusion in clipboard_exclusions(private) {
+        set = match exclusion {
+            ClipboardExclusion::Monitoring => set.exclude_from_monitoring(),
+            ClipboardExclusion::History => set.exclude_from_history(),
+            ClipboardExclusion::Cloud => set.exclude_from_cloud(),
+        };
+    }
+    set.text(value.to_string())
+        .map_err(|_| ClipboardError::WriteFailed)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn copy_to_clipboard(_: &str, _: bool) -> Result<(), ClipboardError> {
+    Err(ClipboardError::Unsupported)
+}
+
+fn ui(frame: &mut Frame, app: &App) {
+    let theme = palette(app.theme);
+    let size = frame.area();
+    let outer = Block::default().borders(Borders::ALL).title("GenPass");
+    frame.render_widget(outer, size);
+
+    let inner = size.inner(Margin {
+        vertical: 1,
+        horizontal: 2,
+    });
+
+    let qr = app.show_qr.then(|| render_qr(&app.password));
+    let output_height = match (&qr, &app.batch) {
+        _ if app.history_filter.is_some() => HISTORY_VISIBLE as u16 + 3,
+        _ if app.profile_selected.is_some() => app.profiles.len() as u16 + 2,
+        (Some(Ok(lines)), _) => lines.len() as u16 + 2,
+        (None, Some(batch)) => batch.len() as u16 + 3,
+        _ => 5,
+    };
+
+    let compact = app.compact || size.height < COMPACT_HEIGHT;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(ui_constraints(
+            compact,
+            output_height,
+            app.show_breakdown,
+            app.show_phonetic,
+        ))
+        .split(inner);
+
+    let mut header_lines = vec![Line::from(vec![
+        Span::styled(
+            "Password Generator",
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(if compact {
+            "  [z] full layout"
+        } else {
+            "  Use arrows (h, j, k, l) or +/- to adjust. Enter to generate."
+        }),
+    ])];
+    if !compact && let Some(advice) = entropy_advice(app) {
+        header_lines.push(Line::from(Span::styled(
+            advice,
+            Style::default().fg(theme.inactive),
+        )));
+    }
+    let header = Paragraph::new(header_lines);
+    frame.render_widget(header, chunks[0]);
+
+    if compact {
+        frame.render_widget(Paragraph::new(compact_fields_line(app, &theme)), chunks[1]);
+        frame.render_widget(Paragraph::new(compact_actions_line(app, &theme)), chunks[2]);
+    } else {
+        render_fields(frame, app, &theme, chunks[1]);
+        render_actions(frame, app, &theme, chunks[2]);
+    }
+
+    let strength_color = strength_color_for(app.strength, &theme);
+
+    let bits = if app.password.is_empty() {
+        0.0
+    } else {
+        estimate_entropy(app)
+    };
+    let (gauge_color, gauge_ratio) = gauge_style(bits, &app.thresholds, &theme);
+    let (gauge_title, gauge_label) = if app.bits_gauge {
+        ("Entropy", format!("{bits:.0} bits"))
+    } else {
+        ("Strength", app.strength.label().to_string())
+    };
+    let gauge = Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title(gauge_title))
+        .gauge_style(Style::default().fg(gauge_color))
+        .ratio(gauge_ratio)
+        .label(gauge_label);
+
+    let output_border = if app.weak_flash_active() {
+        Style::default().fg(theme.bad)
+    } else {
+        Style::default()
+    };
+    let display_len = app.display_password().chars().count();
+    let max_scroll = max_output_scroll(display_len, chunks[3].width.saturating_sub(2));
+    let output_scroll = app.output_scroll.min(max_scroll);
+    let mut output_title = if app.copy_blocked() {
+        "Output (used)".to_string()
+    } else {
+        "Output".to_string()
+    };
+    if !app.password.is_empty() {
+        output_title.push_str(&format!(" [check {}]", checksum(&app.password)));
+    }
+    if output_scroll < max_scroll {
+        output_title.push_str(" \u{25be} more");
+    }
+    let output = match qr {
+        Some(Ok(lines)) => Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(output_border)
+                    .title("Output (QR)"),
+            ),
+        Some(Err(message)) => Paragraph::new(Line::from(Span::styled(
+            message,
+            Style::default().fg(theme.bad),
+        )))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(output_border)
+                .title("Output (QR)"),
+        ),
+        None => Paragraph::new(vec![
+            Line::from(vec![Span::styled(
+                "Generated Password",
+                Style::default().add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(app.password_spans(&theme)),
+            Line::from(vec![Span::styled(
+                format!(
+                    "Strength: {} (~{:.0} bits)",
+                    app.strength.label(),
+                    estimate_entropy(app)
+                ),
+                Style::default().fg(strength_color),
+            )]),
+        ])
+        .wrap(Wrap { trim: false })
+        .scroll((output_scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(output_border)
+                .title(output_title),
+        ),
+    };
+
+    // render widgets
+    frame.render_widget(gauge, chunks[4]);
+    let gauge_inner = chunks[4].inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    if let Some(target) = target_bits(app.min_strength)
+        && gauge_inner.width > 0
+        && gauge_inner.height > 0
+    {
+        let x = marker_x(gauge_inner, target / app.thresholds.full);
+        if let Some(cell) = frame.buffer_mut().cell_mut((x, gauge_inner.y)) {
+            cell.set_symbol("\u{2502}").set_fg(theme.active);
+        }
+    }
+    match &app.compare_slot {
+        _ if let Some(query) = &app.history_filter => {
+            let matches = app.history_matches();
+            let first = app.history_selected.saturating_sub(HISTORY_VISIBLE - 1);
+            let match_style = Style::default()
+                .fg(theme.active)
+                .add_modifier(Modifier::BOLD);
+            let mut lines = vec![Line::from(vec![
+                Span::raw("/"),
+                Span::styled(format!("{query}_"), match_style),
+                Span::styled(
+                    format!("  {} of {}", matches.len(), app.history.len()),
+                    Style::default().fg(theme.inactive),
+                ),
+            ])];
+            for (position, &index) in matches.iter().enumerate().skip(first).take(HISTORY_VISIBLE) {
+                let marker = if position == app.history_selected {
+                    "> "
+                } else {
+                    "  "
+                };
+                let mut spans = vec![Span::raw(marker)];
+                spans.extend(highlight_spans(&app.history[index], query, match_style));
+                lines.push(Line::from(spans));
+            }
+            let list = Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("History (Enter select, Esc close)"),
+            );
+            frame.render_widget(list, chunks[3]);
+        }
+        _ if let Some(selected) = app.profile_selected => {
+            let lines: Vec<Line> = app
+                .profile_names()
+                .into_iter()
+                .enumerate()
+                .map(|(index, name)| {
+                    let style = if index == selected {
+                        Style::default()
+                            .fg(theme.active)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(theme.inactive)
+                    };
+                    let marker = if index == selected { "> " } else { "  " };
+                    Line::from(Span::styled(
+                        format!("{marker}{name:<16}{}", spec_summary(&app.profiles[&name])),
+                        style,
+                    ))
+                })
+                .collect();
+            let list = Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Profiles (Enter load, Esc close)"),
+            );
+            frame.render_widget(list, chunks[3]);
+        }
+        _ if let Some(batch) = &app.batch
+            && !app.show_qr =>
+        {
+            let rows = batch.iter().enumerate().map(|(index, row)| {
+                Row::new(vec![
+                    Cell::from(format!("{}", index + 1)),
+                    Cell::from(Line::from(class_colored_spans(&row.password, &theme))),
+                    Cell::from(row.strength.label())
+                        .style(Style::default().fg(strength_color_for(row.strength, &theme))),
+                    Cell::from(format!("{:.0}", row.bits)),
+                ])
+            });
+            let width = batch
+                .iter()
+                .map(|row| row.password.chars().count())
+                .max()
+                .unwrap_or(0) as u16;
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(3),
+                    Constraint::Length(width.max(8)),
+                    Constraint::Length(14),
+                    Constraint::Length(4),
+                ],
+            )
+            .header(
+                Row::new(vec!["#", "Password", "Strength", "Bits"])
+                    .style(Style::default().fg(theme.inactive)),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(theme.active)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            )
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Batch of {} (# or Enter copy, Ctrl+R regenerate, +/- count, M close)",
+                app.batch_size
+            )));
+            let mut state = TableState::default().with_selected(Some(app.batch_selected));
+            frame.render_stateful_widget(table, chunks[3], &mut state);
+        }
+        Some(pinned) if !app.show_qr => {
+            let halves = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(chunks[3]);
+            let candidates = [
+                ("Pinned", pinned.as_str(), app.compare_pinned_active),
+                ("Current", app.password.as_str(), !app.compare_pinned_active),
+            ];
+            for ((title, password, active), area) in candidates.into_iter().zip(halves.iter()) {
+                let title = if active {
+                    format!("{title} (copy)")
+                } else {
+                    title.to_string()
+                };
+                let border_style = if active {
+                    Style::default().fg(theme.active)
+                } else {
+                    Style::default()
+                };
+                let pane = Paragraph::new(vec![
+                    Line::from(class_colored_spans(password, &theme)),
+                    Line::from(Span::raw(format!(
+                        "Strength: {}",
+                        app.affixed_strength(password).label()
+                    ))),
+                    Line::from(Span::styled(
+                        "[w] swap  [B] close",
+                        Style::default().fg(theme.inactive),
+                    )),
+                ])
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(border_style)
+                        .title(title),
+                );
+                frame.render_widget(pane, *area);
+            }
+        }
+        _ => frame.render_widget(output, chunks[3]),
+    }
+
+    if app.show_breakdown {
+        let lines: Vec<Line> = strength_criteria(app.active_password(), app.strength_min_length)
+            .into_iter()
+            .map(|(name, met)| {
+                let (mark, color) = if met {
+                    ("✓", theme.good)
+                } else {
+                    ("✗", theme.bad)
+                };
+                Line::from(vec![
+                    Span::styled(format!("{mark} "), Style::default().fg(color)),
+                    Span::raw(name),
+                ])
+            })
+            .collect();
+        let breakdown =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Breakdown"));
+        frame.render_widget(breakdown, chunks[5]);
+    }
+
+    if app.show_phonetic {
+        let phonetic = Paragraph::new(phonetic(app.active_password()).join(" "))
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title("Phonetic"));
+        frame.render_widget(phonetic, chunks[6]);
+    }
+
+    let status = app.status_line();
+    if let Some(input) = &app.profile_name {
+        let style = Style::default()
+            .fg(theme.active)
+            .add_modifier(Modifier::BOLD);
+        let mut spans = vec![Span::styled("Save profile as: ", style)];
+        spans.extend(input.spans(style));
+        frame.render_widget(Paragraph::new(Line::from(spans)), chunks[8]);
+    } else if !status.is_empty() {
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                status,
+                Style::default().fg(theme.status),
+            ))),
+            chunks[8],
+        );
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(
+            footer_text(app.rng_source, &app.key_hints),
+            Style::default()
+                .fg(theme.inactive)
+                .add_modifier(Modifier::DIM),
+        ))),
+        chunks[9],
+    );
+
+    if app.show_key_log {
+        let height = (app.key_log.len() as u16 + 2).max(3);
+        let area = Rect {
+            x: inner.x + inner.width.saturating_sub(36) / 2,
+            y: inner.y + inner.height.saturating_sub(height) / 2,
+            width: inner.width.min(36),
+            height: inner.height.min(height),
+        };
+        let lines: Vec<Line> = app
+            .key_log
+            .iter()
+            .map(|entry| Line::from(entry.as_str()))
+            .collect();
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Unhandled keys (F12 close)"),
+            ),
+            area,
+        );
+    }
+
+    if app.show_stats {
+        let area = Rect {
+            x: inner.x + inner.width.saturating_sub(24) / 2,
+            y: inner.y + inner.height.saturating_sub(4) / 2,
+            width: inner.width.min(24),
+            height: inner.height.min(4),
+        };
+        let lines: Vec<Line> = stats_lines(app).into_iter().map(Line::from).collect();
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Session (I close)"),
+            ),
+            area,
+        );
+    }
+}
+
+fn field_labels(app: &App) -> [(&'static str, i32); 4] {
+    [
+        match app.mode {
+            Mode::Random if app.uses_length_only() => ("Length", total_length(app)),
+            Mode::Random if app.uses_case_ratio() => ("Letters", app.letters + app.uppercase),
+            Mode::Random => ("Letters", app.letters),
+            Mode::Pronounceable => ("Syllables", app.letters),
+            Mode::Pin => ("Digits", app.pin_digits),
+            Mode::Passphrase => ("Words", app.passphrase_words),
+            Mode::Combo => ("Digits", app.combo_digits),
+        },
+        if app.uses_case_ratio() {
+            ("Uppercase %", app.upper_pct)
+        } else {
+            ("Uppercase", app.uppercase)
+        },
+        ("Symbols", app.symbols),
+        ("Numbers", app.numbers),
+    ]
+}
+
+fn render_fields(frame: &mut Frame, app: &App, theme: &Theme, area: Rect) {
+    let fields = field_labels(app);
+
+    let field_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1); 10])
+        .split(area);
+
+    for (index, ((label, value), area)) in fields.iter().zip(field_chunks.iter()).enumerate() {
+        if !app.field_visible(index) {
+            continue;
+        }
+        let is_active = index == app.focus;
+        let line = Line::from(vec![
+            Span::styled(
+                format!("{label:<12}"),
+                if is_active {
+                    Style::default()
+                        .fg(theme.active)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                },
+            ),
+            Span::raw("  "),
+            Span::styled(
+                match &app.field_edit {
+                    Some(input) if is_active => format!("{input:>3}_"),
+                    _ => format!("{value:>3}"),
+                },
+                if is_active {
+                    Style::default()
+                        .fg(theme.active)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.inactive)
+                },
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(line), *area);
+    }
+
+    let editing_style = Style::default()
+        .fg(theme.active)
+        .add_modifier(Modifier::BOLD);
+    let mut symbol_spans = vec![Span::raw(format!("{:<12}", "Symbol set")), Span::raw("  ")];
+    match &app.symbol_edit {
+        Some(input) => symbol_spans.extend(input.spans(editing_style)),
+        None => symbol_spans.push(Span::styled(
+            format!("{}  [S] edit", app.symbol_set.iter().collect::<String>()),
+            Style::default().fg(theme.inactive),
+        )),
+    }
+    symbol_spans.push(Span::raw("  "));
+    match &app.exclude_edit {
+        Some(input) => {
+            symbol_spans.push(Span::styled("Exclude: ", editing_style));
+            symbol_spans.extend(input.spans(editing_style));
+        }
+        None => symbol_spans.push(Span::styled(
+            format!(
+                "Exclude: {}  [X] edit",
+                app.exclude.iter().collect::<String>()
+            ),
+            Style::default().fg(theme.inactive),
+        )),
+    }
+    let symbol_line = Line::from(symbol_spans);
+    frame.render_widget(Paragraph::new(symbol_line), field_chunks[4]);
+
+    let total = total_length(app);
+    let total_line = Line::from(vec![
+        Span::raw(format!("{:<12}", "Total")),
+        Span::raw("  "),
+        Span::styled(
+            format!("{total:>3} chars"),
+            Style::default()
+                .fg(total_length_color(total, theme))
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(total_line), field_chunks[5]);
+
+    let options_line = Line::from(vec![
+        Span::raw(format!("{:<12}", "Options")),
+        Span::raw("  "),
+        Span::styled(
+            format!(
+                "[p] Mode: {}  [s] No sequences: {}  [x] Shell-safe: {}  [F] Leading letter: {}  [G] Group: {}{}",
+                app.mode.label(),
+                if app.no_sequences { "on" } else { "off" },
+                if app.shell_safe { "on" } else { "off" },
+                if app.leading_letter { "on" } else { "off" },
+                app.group_size
+                    .map_or("off".to_string(), |size| size.to_string()),
+                if app.copy_grouped {
+                    " [y] copy grouped"
+                } else {
+                    ""
+                }
+            ),
+            Style::default().fg(theme.inactive),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(options_line), field_chunks[6]);
+
+    let preset_line = Line::from(vec![
+        Span::raw(format!("{:<12}", "Preset")),
+        Span::raw("  "),
+        Span::styled(
+            format!(
+                "[P] {}  [a] No ambiguous: {}  [u] Case ratio: {}  [D] Length only: {}  [T] Theme: {}",
+                app.preset.map_or("Custom", Preset::label),
+                if app.no_ambiguous { "on" } else { "off" },
+                if app.case_ratio { "on" } else { "off" },
+                if app.length_only { "on" } else { "off" },
+                app.theme.label()
+            ),
+            Style::default().fg(theme.inactive),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(preset_line), field_chunks[7]);
+
+    let mut label_spans = vec![Span::raw(format!("{:<12}", "Site")), Span::raw("  ")];
+    match &app.label_edit {
+        Some(input) => label_spans.extend(input.spans(editing_style)),
+        None => label_spans.push(Span::styled(
+            if app.label.is_empty() {
+                "[n] add a label, [m] copy with label".to_string()
+            } else {
+                app.label.clone()
+            },
+            Style::default().fg(theme.inactive),
+        )),
+    }
+    let label_line = Line::from(label_spans);
+    frame.render_widget(Paragraph::new(label_line), field_chunks[8]);
+
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(
+            field_help(app.focus),
+            Style::default()
+                .fg(theme.inactive)
+                .add_modifier(Modifier::ITALIC),
+        ))),
+        field_chunks[9],
+    );
+}
+
+fn field_help(focus: usize) -> &'static str {
+    match focus {
+        0 => "Number of lowercase letters a\u{2013}z",
+        1 => "Number of uppercase letters A\u{2013}Z",
+        2 => "Number of symbols drawn from the symbol set",
+        3 => "Number of digits 0\u{2013}9",
+        FOCUS_GENERATE => "Generate a new password with these settings",
+        FOCUS_COPY => "Copy the password to the clipboard",
+        FOCUS_QUIT => "Quit GenPass",
+        _ => "",
+    }
+}
+
+fn render_actions(frame: &mut Frame, app: &App, theme: &Theme, area: Rect) {
+    let actions = ["Generate", "Copy to clipboard", "Quit"];
+    let actions_block = Block::default().borders(Borders::ALL).title("Actions");
+    frame.render_widget(actions_block, area);
+    let inner_actions = area.inner(Margin {
+        vertical: 1,
+        horizontal: 2,
+    });
+    let action_rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(inner_actions);
+
+    for (index, label) in actions.iter().enumerate() {
+        let focus_index = FOCUS_GENERATE + index;
+        let is_active = app.focus == focus_index;
+        let style = if is_active {
+            Style::default()
+                .fg(theme.active)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.inactive)
+        };
+        let line = Line::from(vec![Span::styled(format!("> {label}"), style)]);
+        frame.render_widget(Paragraph::new(line), action_rows[index]);
+    }
+}
+
+fn compact_fields_line(app: &App, theme: &Theme) -> Line<'static> {
+    let mut spans = Vec::new();
+    for (index, (label, value)) in field_labels(app).into_iter().enumerate() {
+        if !app.field_visible(index) {
+            continue;
+        }
+        let is_active = index == app.focus;
+        let short = label.chars().next().unwrap_or(' ');
+        let suffix = if label.ends_with('%') { "%" } else { "" };
+        let text = match &app.field_edit {
+            Some(input) if is_active => format!("{short}{suffix}{input}_"),
+            _ => format!("{short}{suffix}{value}"),
+        };
+        let style = if is_active {
+            Style::default()
+                .fg(theme.active)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.inactive)
+        };
+        spans.push(Span::styled(text, style));
+        spans.push(Span::raw(" "));
+    }
+    let total = total_length(app);
+    spans.push(Span::styled(
+        format!(" = {total}"),
+        Style::default()
+            .fg(total_length_color(total, theme))
+            .add_modifier(Modifier::BOLD),
+    ));
+    spans.push(Span::styled(
+        format!("  {}", app.mode.label()),
+        Style::default().fg(theme.inactive),
+    ));
+    Line::from(spans)
+}
+
+fn compact_actions_line(app: &App, theme: &Theme) -> Line<'static> {
+    // the full layout has a row per edit buffer; compact mode borrows this one
+    if let Some((label, input)) = app.active_text_edit() {
+        let editing_style = Style::default()
+            .fg(theme.active)
+            .add_modifier(Modifier::BOLD);
+        let mut spans = vec![Span::styled(format!("{label}: "), editing_style)];
+        spans.extend(input.spans(editing_style));
+        return Line::from(spans);
+    }
+    let actions = ["Generate", "Copy", "Quit"];
+    let mut spans = Vec::new();
+    for (index, label) in actions.iter().enumerate() {
+        let style = if app.focus == FOCUS_GENERATE + index {
+            Style::default()
+                .fg(theme.active)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.inactive)
+        };
+        spans.push(Span::styled(format!("> {label}  "), style));
+    }
+    Line::from(spans)
+}
+
+fn ui_constraints(
+    compact: bool,
+    output_height: u16,
+    show_breakdown: bool,
+    show_phonetic: bool,
+) -> [Constraint; 10] {
+    let (header, fields, actions) = if compact { (1, 1, 1) } else { (3, 10, 5) };
+    [
+        Constraint::Length(header),
+        Constraint::Length(fields),
+        Constraint::Length(actions),
+        Constraint::Length(output_height),
+        Constraint::Length(if compact { 3 } else { 5 }),
+        Constraint::Length(if show_breakdown { 7 } else { 0 }),
+        Constraint::Length(if show_phonetic { 4 } else { 0 }),
+        Constraint::Min(0),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ]
+}
+
+fn stats_lines(app: &App) -> [String; 2] {
+    [
+        format!("Generated  {}", app.generated_total),
+        format!("Copied     {}", app.copied_total),
+    ]
+}
+
+fn announce_generated(chars: usize, strength: Strength) -> String {
+    format!("password generated, {chars} chars, {}", strength.label())
+}
+
+fn announce_focus(app: &App) -> String {
+    match app.focus {
+        FOCUS_GENERATE => "Generate button".to_string(),
+        FOCUS_COPY => "Copy button".to_string(),
+        FOCUS_QUIT => "Quit button".to_string(),
+        focus => {
+            let (label, value) = field_labels(app)[focus.min(FOCUS_FIELDS - 1)];
+            format!("{label}: {value}")
+        }
+    }
+}
+
+const NATO: [&str; 26] = [
+    "alfa", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliett",
+    "kilo", "lima", "mike", "november", "oscar", "papa", "quebec", "romeo", "sierra", "tango",
+    "uniform", "victor", "whiskey", "xray", "yankee", "zulu",
+];
+
+const DIGIT_NAMES: [&str; 10] = [
+    "Zero", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Nine",
+];
+
+fn symbol_name(ch: char) -> Option<&'static str> {
+    Some(match ch {
+        ' ' => "Space",
+        '!' => "Exclamation",
+        '"' => "Double quote",
+        '#' => "Hash",
+        '$' => "Dollar",
+        '%' => "Percent",
+        '&' => "Ampersand",
+        '\'' => "Apostrophe",
+        '(' => "Open paren",
+        ')' => "Close paren",
+        '*' => "Asterisk",
+        '+' => "Plus",
+        ',' => "Comma",
+        '-' => "Dash",
+        '.' => "Period",
+        '/' => "Slash",
+        ':' => "Colon",
+        ';' => "Semicolon",
+        '<' => "Less than",
+        '=' => "Equals",
+        '>' => "Greater than",
+        '?' => "Question",
+        '@' => "At",
+        '[' => "Open bracket",
+        '\\' => "Backslash",
+        ']' => "Close bracket",
+        '^' => "Caret",
+        '_' => "Underscore",
+        '`' => "Backtick",
+        '{' => "Open brace",
+        '|' => "Pipe",
+        '}' => "Close brace",
+        '~' => "Tilde",
+        _ => return None,
+    })
+}
+
+fn phonetic(password: &str) -> Vec<String> {
+    password
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_lowercase() {
+                NATO[(ch as u8 - b'a') as usize].to_string()
+            } else if ch.is_ascii_uppercase() {
+                // shouted so the case survives being read aloud
+                NATO[(ch as u8 - b'A') as usize].to_ascii_uppercase()
+            } else if let Some(digit) = ch.to_digit(10) {
+                DIGIT_NAMES[digit as usize].to_string()
+            } else {
+                symbol_name(ch).map_or_else(|| ch.to_string(), str::to_string)
+            }
+        })
+        .collect()
+}
+
+fn footer_text(source: RngSource, hints: &str) -> String {
+    format!(
+        "GenPass v{}  {}  {hints}",
+        env!("CARGO_PKG_VERSION"),
+        source.label()
+    )
+}
+
+fn run_app(terminal: &mut Terminal<CrosstermBackend<Stdout>>, config: &Config) -> io::Result<()> {
+    let mut app = App::new(config);
+    let mut count = CountPrefix::default();
+
+    loop {
+        if std::mem::take(&mut app.bell_pending) {
+            // through the backend so it is ordered with the frame output
+            let backend = terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            backend.flush()?;
+        }
+        app.tick();
+        if app.a11y {
+            // plain lines for a screen reader; never the password itself
+            for line in app.announcements() {
+                eprintln!("{line}");
+            }
+        }
+        if app.dirty {
+            terminal.draw(|frame| ui(frame, &app))?;
+            app.dirty = false;
+        }
+
+        let event = if event::poll(Duration::from_millis(50))? {
+            Some(event::read()?)
+        } else {
+            None
+        };
+        if let Some(Event::Resize(width, _)) = event {
+            app.resize(width);
+            // redraw now rather than waiting for the next key
+            continue;
+        }
+        if let Some(Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind,
+            ..
+        })) = event
+        {
+            if kind != KeyEventKind::Press {
+                continue;
+            }
+            // any key can change what is on screen
+            app.dirty = true;
+            // before the prompts, which would otherwise take it as a typed 'c'
+            match handle_modal_key(&mut app, code, modifiers) {
+                KeyFlow::Quit => return Ok(()),
+                KeyFlow::Handled => continue,
+                KeyFlow::Unhandled => {}
+            }
+            if app.batch.is_some() {
+                if !matches!(code, KeyCode::Char('0'..='9')) {
+                    app.batch_typed = None;
+                }
+                match (code, modifiers) {
+                    (KeyCode::Char(ch @ '0'..='9'), _) => {
+                        if let Some(digit) = ch.to_digit(10) {
+                            app.type_batch_digit(digit as usize);
+                        }
+                        continue;
+                    }
+                    (KeyCode::Enter, _) => {
+                        app.copy_batch_row(app.batch_selected);
+                        continue;
+                    }
+                    (KeyCode::Up | KeyCode::Char('k'), _) => {
+                        app.move_batch_selection(-1);
+                        continue;
+                    }
+                    (KeyCode::Down | KeyCode::Char('j'), _) => {
+                        app.move_batch_selection(1);
+                        continue;
+                    }
+                    (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                        app.regenerate_batch();
+                        continue;
+                    }
+                    (KeyCode::Char('+'), _) => {
+                        app.resize_batch(1);
+                        continue;
+                    }
+                    (KeyCode::Char('-'), _) => {
+                        app.resize_batch(-1);
+                        continue;
+                    }
+                    (KeyCode::Esc, _) => {
+                        app.toggle_batch();
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            if let KeyCode::Char(ch) = code
+                && let Some(digit) = ch.to_digit(10)
+                && count.push_digit(digit)
+            {
+                continue;
+            }
+            let repeat = count.take();
+            let action = config.keymap.get(&KeyCombo::new(code, modifiers)).copied();
+            match action {
+                Some(Action::Quit) => return Ok(()),
+                Some(Action::FocusUp) => app.move_focus(-(repeat as isize)),
+                Some(Action::FocusDown) => app.move_focus(repeat as isize),
+                Some(Action::Dec) => app.step_value(-1, repeat),
+                Some(Action::Inc) => app.step_value(1, repeat),
+                Some(Action::Copy) => {
+                    app.copy_password();
+                }
+                Some(Action::Generate) => {
+                    if app.focus >= FOCUS_FIELDS {
+                        match app.focus {
+                            FOCUS_GENERATE => {
+                                app.regenerate();
+                            }
+                            FOCUS_COPY => {
+                                app.copy_password();
+                            }
+                            FOCUS_QUIT => return Ok(()),
+                            _ => {}
+                        }
+                    } else {
+                        app.regenerate();
+                    }
+                }
+                None => {}
+            }
+            if action.is_some() {
+                continue;
+            }
+            match (code, modifiers) {
+                (code, _) if let Some(action) = accelerator(code) => app.run_accelerator(action),
+                (KeyCode::Tab, _) => app.cycle_focus(1),
+                (KeyCode::BackTab, _) => app.cycle_focus(-1),
+                (KeyCode::Char('m'), _) => {
+                    app.copy_with_metadata();
+                }
+                (KeyCode::Char('A'), _) => app.copy_spec_command(),
+                (KeyCode::Char('_'), _) => {
+                    app.toggle_space();
+                    app.generate_password();
+                }
+                (KeyCode::Char('n'), _) => app.label_edit = Some(TextInput::new(&app.label)),
+                (KeyCode::Char('v'), _) => app.show_qr = !app.show_qr,
+                (KeyCode::Char('M'), _) => app.toggle_batch(),
+                (KeyCode::Char('e'), _) => app.show_breakdown = !app.show_breakdown,
+                (KeyCode::Char('N'), _) => app.show_phonetic = !app.show_phonetic,
+                (KeyCode::Char('f'), _) => app.reroll_focused(),
+                (KeyCode::Char('t'), _) => app.toggle_clipboard_clear_pause(),
+                (KeyCode::Char('z'), _) => app.compact = !app.compact,
+                (KeyCode::Char('I'), _) => app.show_stats = !app.show_stats,
+                // never H/J/K/L: with Caps Lock on those are the motion keys
+                (KeyCode::Char('V'), _) => app.start_type_countdown(),
+                (KeyCode::Char('o'), _) => app.open_profiles(),
+                (KeyCode::Char('O'), _) => app.profile_name = Some(TextInput::default()),
+                (KeyCode::Char('G'), _) => app.cycle_group_size(),
+                (KeyCode::Char('E'), _) => app.bits_gauge = !app.bits_gauge,
+                (KeyCode::Char('y'), _) => app.copy_grouped = !app.copy_grouped,
+                (KeyCode::Char(':'), _) | (KeyCode::Char('i'), _) => app.begin_field_edit(),
+                (KeyCode::Char('p'), _) => {
+                    app.switch_mode(app.next_mode());
+                    app.generate_password();
+                }
+                (KeyCode::Char('P'), _) => {
+                    app.apply_preset(Preset::next(app.preset));
+                    app.generate_password();
+                }
+                (KeyCode::Char('a'), _) => {
+                    app.no_ambiguous = !app.no_ambiguous;
+                    app.preset = None;
+                    app.generate_password();
+                }
+                (KeyCode::Char('u'), _) => app.toggle_case_ratio(),
+                (KeyCode::PageDown, _) => {
+                    app.scroll_output(1, output_inner_width(terminal.size()?.width));
+                }
+                (KeyCode::PageUp, _) => {
+                    app.scroll_output(-1, output_inner_width(terminal.size()?.width));
+                }
+                (KeyCode::Char('D'), _) => {
+                    app.toggle_length_only();
+                    app.generate_password();
+                }
+                (KeyCode::F(n), _) if let Some(class) = CharClass::from_function_key(n) => {
+                    app.toggle_class(class);
+                    app.generate_password();
+                }
+                (KeyCode::Char('T'), _) => {
+                    app.theme = app.theme.next();
+                    if let Some(path) = settings_path() {
+                        let _ = save_theme(&path, app.theme);
+                    }
+                }
+                (KeyCode::Char('S'), _) => app.open_symbol_edit(),
+                (KeyCode::Char('X'), _) => app.open_exclude_edit(),
+                (KeyCode::Char('/'), _) => {
+                    app.history_filter = Some(String::new());
+                    app.history_selected = 0;
+                }
+                (KeyCode::Char('b'), _) => app.pin_compare(),
+                (KeyCode::Char('B'), _) => app.clear_compare(),
+                (KeyCode::Char('w'), _) => app.swap_compare(),
+                (KeyCode::Char('x'), _) => {
+                    app.shell_safe = !app.shell_safe;
+                    app.status = if app.shell_safe {
+                        let remaining = filtered_pool(&app.symbol_set, SHELL_UNSAFE);
+                        format!(
+                            "Shell-safe symbols: {}",
+                            remaining.iter().collect::<String>()
+                        )
+                    } else {
+                        "All symbols enabled.".to_string()
+                    };
+                    app.status_until = Some(Instant::now() + CLIPBOARD_MESSAGE_DURATION);
+                    app.generate_password();
+                }
+                (KeyCode::Char('s'), _) => {
+                    app.no_sequences = !app.no_sequences;
+                    app.generate_password();
+                }
+                (KeyCode::Char('F'), _) => {
+                    app.leading_letter = !app.leading_letter;
+                    app.generate_password();
+                }
+                (KeyCode::Char('Z'), _) => {
+                    app.no_leading_zero = !app.no_leading_zero;
+                    app.generate_password();
+                }
+                (KeyCode::Char('U'), _) => {
+                    app.toggle_printable_only();
+                    app.generate_password();
+                }
+                (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                    app.regenerate();
+                }
+                (KeyCode::F(n @ 5..=9), _) => {
+                    app.toggle_confusable(usize::from(n - 5));
+                    app.generate_password();
+                }
+                (KeyCode::F(12), _) => app.show_key_log = !app.show_key_log,
+                (KeyCode::Char('W'), _) => app.clear_password(),
+                _ => app.handle_unhandled_key(code, modifiers, &config.keymap, repeat),
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum KeyFlow {
+    Quit,
+    Handled,
+    Unhandled,
+}
+
+/// Handles a key while a prompt, picker or countdown has the keyboard;
+/// Ctrl-C quits from any of them.
+fn handle_modal_key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) -> KeyFlow {
+    if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+        return KeyFlow::Quit;
+    }
+    if app.type_countdown.is_some() {
+        if code == KeyCode::Esc {
+            app.cancel_type_countdown();
+        }
+        return KeyFlow::Handled;
+    }
+    if app.field_edit.is_some() {
+        match code {
+            KeyCode::Enter => {
+                app.commit_field_edit();
+                app.regenerate();
+            }
+            KeyCode::Esc => app.field_edit = None,
+            KeyCode::Backspace => {
+                if let Some(input) = &mut app.field_edit {
+                    input.pop();
+                }
+            }
+            KeyCode::Char('v') if modifiers.contains(KeyModifiers::CONTROL) => {
+                app.paste_into(TextField::Count);
+            }
+            KeyCode::Char(ch) if !modifiers.intersects(TYPING_MODIFIERS) => {
+                app.push_field_edit(ch);
+            }
+            _ => {}
+        }
+        return KeyFlow::Handled;
+    }
+    if app.symbol_edit.is_some() {
+        match code {
+            KeyCode::Enter => {
+                app.commit_symbol_edit();
+                app.generate_password();
+            }
+            KeyCode::Esc => app.symbol_edit = None,
+            KeyCode::Char('v') if modifiers.contains(KeyModifiers::CONTROL) => {
+                app.paste_into(TextField::Symbols);
+            }
+            code => {
+                if let Some(input) = &mut app.symbol_edit {
+                    input.handle_key(code, modifiers);
+                }
+            }
+        }
+        return KeyFlow::Handled;
+    }
+    if app.exclude_edit.is_some() {
+        match code {
+            KeyCode::Enter => {
+                app.commit_exclude_edit();
+                app.generate_password();
+            }
+            KeyCode::Esc => app.exclude_edit = None,
+            KeyCode::Char('v') if modifiers.contains(KeyModifiers::CONTROL) => {
+                app.paste_into(TextField::Exclude);
+            }
+            code => {
+                if let Some(input) = &mut app.exclude_edit {
+                    input.handle_key(code, modifiers);
+                }
+            }
+        }
+        return KeyFlow::Handled;
+    }
+    if let Some(input) = &mut app.label_edit {
+        match code {
+            KeyCode::Enter | KeyCode::Esc => {
+                app.label = input.text.clone();
+                app.label_edit = None;
+            }
+            KeyCode::Char('v') if modifiers.contains(KeyModifiers::CONTROL) => {
+                app.paste_into(TextField::Label);
+            }
+            code => input.handle_key(code, modifiers),
+        }
+        return KeyFlow::Handled;
+    }
+    if let Some(input) = &mut app.profile_name {
+        match code {
+            KeyCode::Enter => {
+                let name = input.text.clone();
+                app.profile_name = None;
+                app.save_profile(&name);
+            }
+            KeyCode::Esc => app.profile_name = None,
+            code => input.handle_key(code, modifiers),
+        }
+        return KeyFlow::Handled;
+    }
+    if app.profile_selected.is_some() {
+        match code {
+            KeyCode::Enter => app.load_selected_profile(),
+            KeyCode::Esc => app.profile_selected = None,
+            KeyCode::Up | KeyCode::Char('k') => app.move_profile_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => app.move_profile_selection(1),
+            _ => {}
+        }
+        return KeyFlow::Handled;
+    }
+    if app.history_filter.is_some() {
+        match code {
+            KeyCode::Enter => app.select_history(),
+            KeyCode::Esc => app.history_filter = None,
+            KeyCode::Up => app.move_history_selection(-1),
+            KeyCode::Down => app.move_history_selection(1),
+            KeyCode::Backspace => {
+                if let Some(query) = &mut app.history_filter {
+                    query.pop();
+                }
+                app.history_selected = 0;
+            }
+            KeyCode::Char(ch) if !modifiers.intersects(TYPING_MODIFIERS) => {
+                if let Some(query) = &mut app.history_filter {
+                    query.push(ch);
+                }
+                app.history_selected = 0;
+            }
+            _ => {}
+        }
+        return KeyFlow::Handled;
+    }
+    KeyFlow::Unhandled
+}
+
+const QR_MAX_WIDTH: usize = 57;
+const QR_QUIET_ZONE: usize = 2;
+const MAX_COUNT_PREFIX: u32 = 9999;
+const MAX_FIELD_INPUT_LEN: usize = 6;
+// characters typed with these held are shortcuts, never text
+const TYPING_MODIFIERS: KeyModifiers = KeyModifiers::CONTROL.union(KeyModifiers::ALT);
+
+#[derive(Default)]
+struct CountPrefix {
+    pending: Option<u32>,
+}
+
+impl CountPrefix {
+    fn push_digit(&mut self, digit: u32) -> bool {
+        // a leading zero is not a count, same as in vim
+        if digit == 0 && self.pending.is_none() {
+            return false;
+        }
+        let value = self.pending.unwrap_or(0) * 10 + digit;
+        self.pending = Some(value.min(MAX_COUNT_PREFIX));
+        true
+    }
+
+    fn take(&mut self) -> u32 {
+        self.pending.take().unwrap_or(1)
+    }
+}
+
+fn run_print(config: &Config) -> i32 {
+    let mut app = App::new(config);
+    if let Some(err) = &app.wordlist_error
+        && config.mode.uses_wordlist()
+    {
+        eprintln!("genpass: passphrase mode disabled: {err}");
+    }
+    let short = app.distinct_shortfall(&app.pools());
+    if !short.is_empty() {
+        eprintln!(
+            "genpass: --distinct asks for more {} than the pool holds",
+            short.join("/")
+        );
+        return EXIT_UNMET;
+    }
+    // indicatif draws nothing when stderr is not a terminal
+    let bar = (!config.quiet && config.count > PROGRESS_STEP)
+        .then(|| ProgressBar::new(config.count as u64).with_finish(ProgressFinish::AndClear));
+    let mut progress = |done: usize| {
+        if let Some(bar) = &bar {
+            bar.set_position(done as u64);
+        }
+    };
+    let mut out = io::stdout().lock();
+    let mut streamed = 0;
+    let mut stream = |passwords: &mut Vec<String>| {
+        let first = streamed;
+        streamed += passwords.len();
+        let result = write_entries(&mut out, first, passwords);
+        if let Err(err) = &result {
+            eprintln!("genpass: could not write output: {err}");
+        }
+        result.is_ok()
+    };
+    let mut passwords = Vec::with_capacity(if config.jsonl { 1 } else { config.count });
+    let mut done = 0;
+    if config.count > 1 && app.uses_bulk_path() {
+        let (spec, pools, mut rng) = (app.spec(), app.pools(), app.rng_source.rng());
+        // --jsonl writes a step at a time so memory stays flat
+        let step = if config.jsonl {
+            PROGRESS_STEP
+        } else {
+            config.count
+        };
+        while done < config.count {
+            let chunk = step.min(config.count - done);
+            passwords.extend(generate_many(&spec, &pools, chunk, &mut rng, |n| {
+                progress(done + n)
+            }));
+            done += chunk;
+            if config.jsonl && !stream(&mut passwords) {
+                return 2;
+            }
+        }
+    }
+    for index in done..config.count {
+        if index > 0 {
+            app.generate_password();
+        }
+        if app.strength < app.min_strength {
+            eprintln!(
+                "genpass: could not reach {} strength (best was {})",
+                app.min_strength.label(),
+                app.strength.label()
+            );
+            return EXIT_UNMET;
+        }
+        if app.denied {
+            eprintln!("genpass: every candidate contained denied content");
+            return EXIT_UNMET;
+        }
+        if app.unsafe_chars {
+            eprintln!("genpass: every candidate contained non-printable characters");
+            return EXIT_UNMET;
+        }
+        if app.unmatched {
+            eprintln!("genpass: no candidate matched --match");
+            return EXIT_UNMET;
+        }
+        if app.too_similar {
+            eprintln!("genpass: every candidate shared too many characters with --avoid");
+            return EXIT_UNMET;
+        }
+        passwords.push(app.password.clone());
+        report_progress(index + 1, config.count, &mut progress);
+        if config.jsonl && !stream(&mut passwords) {
+            return 2;
+        }
+    }
+    drop(bar);
+    if config.jsonl {
+        return 0;
+    }
+
+    if config.copy
+        && let Err(err) = copy_to_clipboard(&passwords.join("\n"), !config.clipboard_history)
+    {
+        eprintln!("genpass: {err}");
+        // distinct from 2 so scripts can tell a missing clipboard from a bad spec
+        return 3;
+    }
+    let lines: Vec<String> = if config.exports.is_empty() {
+        passwords
+    } else {
+        config
+            .exports
+            .iter()
+            .zip(&passwords)
+            .map(|(name, password)| export_line(name, password))
+            .collect()
+    };
+    let Some(path) = &config.out_path else {
+        if !config.quiet {
+            for line in &lines {
+                println!("{line}");
+            }
+        }
+        return 0;
+    };
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    if let Err(err) = write_secure(path, &contents, config.force) {
+        if err.kind() == io::ErrorKind::AlreadyExists {
+            eprintln!(
+                "genpass: {} already exists (use --force to overwrite)",
+                path.display()
+            );
+        } else {
+            eprintln!("genpass: could not write {}: {err}", path.display());
+        }
+        return 2;
+    }
+    if cfg!(not(unix)) {
+        eprintln!(
+            "genpass: note: {} was written with default permissions",
+            path.display()
+        );
+    }
+    0
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RunMode {
+    Tui,
+    Print,
+}
+
+fn select_run_mode(stdout_is_terminal: bool, force_print: bool) -> RunMode {
+    if stdout_is_terminal && !force_print {
+        RunMode::Tui
+    } else {
+        RunMode::Print
+    }
+}
+
+trait TerminalRestore {
+    fn restore(&mut self);
+}
+
+struct CrosstermRestore;
+
+impl TerminalRestore for CrosstermRestore {
+    fn restore(&mut self) {
+        // best effort: there is nowhere left to report a failure
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+    }
+}
+
+struct TerminalGuard<R: TerminalRestore> {
+    restore: R,
+}
+
+impl<R: TerminalRestore> Drop for TerminalGuard<R> {
+    fn drop(&mut self) {
+        self.restore.restore();
+    }
+}
+
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // leave the alternate screen first so the message stays visible
+        CrosstermRestore.restore();
+        default_hook(info);
+    }));
+}
+
+fn main() -> io::Result<()> {
+    let mut base = Config::from_env();
+    if let Some(path) = profiles_path() {
+        base.profiles = load_profiles(&path);
+    }
+    let mut config = match Config::from_args_over(base, std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("genpass: {message}");
+            std::process::exit(2);
+        }
+    };
+    if let Some(path) = settings_path() {
+        if config.theme.is_none() {
+            config.theme = load_theme(&path);
+        }
+        config.thresholds = load_thresholds(&path);
+        if config.strength_min_length.is_none() {
+            config.strength_min_length = load_strength_min_length(&path);
+        }
+        let printable_only = config.ascii_printable_only;
+        for (name, affix) in [
+            ("prefix", &mut config.prefix),
+            ("suffix", &mut config.suffix),
+        ] {
+            if affix.is_none() {
+                *affix =
+                    load_affix(&path, name).filter(|value| !printable_only || is_copy_safe(value));
+            }
+        }
+        config.auto_copy |= load_flag(&path, "auto_copy");
+        config.focus_copy_after_generate = load_flag(&path, "focus_copy_after_generate");
+    }
+    if let Some(dir) = config_dir() {
+        config.keymap = load_keymap(&dir.join("keymap.toml"));
+    }
+
+    if config.dry_run {
+        print!("{config}");
+        std::process::exit(0);
+    }
+
+    if let Some(site) = &config.derive_site {
+        let spec = PasswordSpec {
+            letters: Some(config.letters),
+            uppercase: Some(config.uppercase),
+            symbols: Some(config.symbols),
+            numbers: Some(config.numbers),
+            ..PasswordSpec::default()
+        };
+        if !io::stdin().is_terminal() {
+            std::process::exit(run_derive(site, &spec, &mut io::stdin()));
+        }
+        let code = match read_hidden_master() {
+            Ok(master) => run_derive(site, &spec, &mut master.as_bytes()),
+            Err(err) => {
+                eprintln!("genpass: could not read the master password: {err}");
+                2
+            }
+        };
+        std::process::exit(code);
+    }
+
+    if config.check {
+        std::process::exit(run_check(
+            &mut io::stdin(),
+            config.common_check,
+            config.strength_min_length.unwrap_or(STRENGTH_MIN_LENGTH),
+        ));
+    }
+
+    if config.simple {
+        std::process::exit(run_simple(
+            &config,
+            &mut io::stdin().lock(),
+            &mut io::stdout(),
+        ));
+    }
+
+    if select_run_mode(io::stdout().is_terminal(), config.print) == RunMode::Print {
+        std::process::exit(run_print(&config));
+    }
+
+    install_panic_hook();
+    enable_raw_mode()?;
+    let _guard = TerminalGuard {
+        restore: CrosstermRestore,
+    };
+    let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_app(&mut terminal);
-
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
-
-    result
+    run_app(&mut terminal, &config)
 }
 
 #[cfg(test)]
@@ -1,7 +1,24 @@
 use super::*;
 use proptest::prelude::*;
-use rand::rngs::StdRng;
 use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+thread_local! {
+    static COPIED: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+pub(super) fn record_copy(value: &str, _private: bool) -> Result<(), ClipboardError> {
+    COPIED.with(|copied| copied.borrow_mut().push(value.to_string()));
+    Ok(())
+}
+
+fn take_copies() -> Vec<String> {
+    COPIED.with(|copied| std::mem::take(&mut *copied.borrow_mut()))
+}
+
+fn chars(value: &str) -> Vec<char> {
+    value.chars().collect()
+}
 
 #[test]
 fn generate_password_has_expected_length_and_categories() {
@@ -57,58 +74,3624 @@ fn generate_password_only_symbols_has_symbols() {
 #[test]
 fn strength_is_strong_when_all_criteria_met() {
     let password = "Aa1!aaaaaa";
-    assert_eq!(check_password_strength(password), "Strong");
+    assert_eq!(
+        check_password_strength(password, STRENGTH_MIN_LENGTH),
+        Strength::Strong
+    );
 }
 
 #[test]
 fn strength_is_moderate_when_three_criteria_met() {
     let password = "Aa1bbbbbbb";
-    assert_eq!(check_password_strength(password), "Moderate");
+    assert_eq!(
+        check_password_strength(password, STRENGTH_MIN_LENGTH),
+        Strength::Moderate
+    );
 }
 
 #[test]
 fn strength_is_weak_when_two_or_fewer_criteria_met() {
     let password = "Aa1bbbb";
-    assert_eq!(check_password_strength(password), "Weak");
+    assert_eq!(
+        check_password_strength(password, STRENGTH_MIN_LENGTH),
+        Strength::Weak
+    );
 }
 
 #[test]
 fn strength_is_do_not_use_when_few_criteria_met() {
     let password = "aaaa";
-    assert_eq!(check_password_strength(password), "Do not use!!!!");
+    assert_eq!(
+        check_password_strength(password, STRENGTH_MIN_LENGTH),
+        Strength::DoNotUse
+    );
 }
 
-proptest! {
-    #[test]
-    fn generated_password_length_matches_sum(
-        letters in 0i32..20,
-        uppercase in 0i32..20,
-        symbols in 0i32..20,
-        numbers in 0i32..20,
-        seed in any::<u64>(),
-    ) {
-        let mut rng = StdRng::seed_from_u64(seed);
-        let password = generate_password(letters, uppercase, symbols, numbers, &mut rng);
-        let expected_len = (letters + uppercase + symbols + numbers) as usize;
-        prop_assert_eq!(password.len(), expected_len);
+#[test]
+fn raised_max_lets_update_value_climb_past_default() {
+    let config = Config::from_args(["--max".to_string(), "200".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.focus = 0;
+    app.update_value(150);
+    assert_eq!(app.letters, DEFAULT_LETTERS + 150);
+    app.update_value(100);
+    assert_eq!(app.letters, 200);
+}
+
+#[test]
+fn default_max_caps_fields_at_64() {
+    let mut app = App::new(&Config::default());
+    app.focus = 3;
+    app.update_value(100);
+    assert_eq!(app.numbers, DEFAULT_MAX_VALUE);
+}
+
+#[test]
+fn total_length_cap_is_enforced() {
+    let config = Config::from_args(["--max".to_string(), "100000".to_string()]).unwrap();
+    assert_eq!(config.max_per_field, MAX_TOTAL_LENGTH);
+
+    let mut app = App::new(&config);
+    app.focus = 0;
+    app.update_value(MAX_TOTAL_LENGTH);
+    app.focus = 1;
+    app.update_value(MAX_TOTAL_LENGTH);
+    let total = app.letters + app.uppercase + app.symbols + app.numbers;
+    assert_eq!(total, MAX_TOTAL_LENGTH);
+}
+
+#[test]
+fn count_implies_print() {
+    let config = Config::from_args(["--count".to_string(), "5".to_string()]).unwrap();
+    assert_eq!(config.count, 5);
+    assert!(config.print);
+    assert!(!Config::from_args(Vec::<String>::new()).unwrap().print);
+}
+
+#[test]
+fn invalid_max_argument_is_rejected() {
+    assert!(Config::from_args(["--max".to_string()]).is_err());
+    assert!(Config::from_args(["--max".to_string(), "abc".to_string()]).is_err());
+    assert!(Config::from_args(["--max".to_string(), "0".to_string()]).is_err());
+    assert!(Config::from_args(["--bogus".to_string()]).is_err());
+}
+
+#[test]
+fn total_length_sums_all_fields() {
+    let mut app = App::new(&Config::default());
+    app.letters = 3;
+    app.uppercase = 4;
+    app.symbols = 5;
+    app.numbers = 6;
+    assert_eq!(total_length(&app), 18);
+}
+
+#[test]
+fn total_length_color_follows_thresholds() {
+    let theme = palette(ThemeKind::Dark);
+    assert_eq!(total_length_color(0, &theme), Color::Red);
+    assert_eq!(total_length_color(7, &theme), Color::Red);
+    assert_eq!(total_length_color(8, &theme), Color::Yellow);
+    assert_eq!(total_length_color(11, &theme), Color::Yellow);
+    assert_eq!(total_length_color(12, &theme), Color::Green);
+    assert_eq!(total_length_color(64, &theme), Color::Green);
+}
+
+#[test]
+fn sequence_detection_covers_ascending_and_descending() {
+    assert_eq!(find_sequence(&chars("xabcx")), Some(1));
+    assert_eq!(find_sequence(&chars("x987x")), Some(1));
+    assert_eq!(find_sequence(&chars("#$%")), Some(0));
+    assert_eq!(find_sequence(&chars("acegi")), None);
+    assert_eq!(find_sequence(&chars("aab")), None);
+}
+
+#[test]
+fn remove_sequences_gives_up_without_a_way_out() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let exclude: Vec<char> = ('d'..='z').collect();
+    let pools = Pools::new(&chars_of(SYMBOLS), &exclude);
+    // every swap breaks the layout and every other letter is already used
+    let layout = [Some(CharClass::Uppercase); 3];
+    let mut password = chars("abc");
+    assert!(!remove_sequences(
+        &mut password,
+        &layout,
+        &pools,
+        true,
+        &mut rng
+    ));
+    let unique: HashSet<char> = password.iter().copied().collect();
+    assert_eq!(unique.len(), 3);
+
+    // without distinct the redraw may repeat a letter
+    assert!(remove_sequences(
+        &mut password,
+        &layout,
+        &pools,
+        false,
+        &mut rng
+    ));
+}
+
+#[test]
+fn distinct_symbols_stay_distinct_without_sequences() {
+    let args = [
+        "--letters",
+        "0",
+        "--uppercase",
+        "0",
+        "--numbers",
+        "0",
+        "--symbols",
+        "3",
+        "--symbol-set",
+        "-./",
+        "--distinct",
+        "--no-sequences",
+    ];
+    let mut app = App::new(&Config::from_args(args.map(String::from)).unwrap());
+    for _ in 0..50 {
+        app.generate_password();
+        let chars: Vec<char> = app.password.chars().collect();
+        let unique: HashSet<char> = chars.iter().copied().collect();
+        assert_eq!(unique.len(), 3, "{}", app.password);
+        assert_eq!(find_sequence(&chars), None, "{}", app.password);
     }
+}
 
-    #[test]
-    fn generated_password_only_has_allowed_chars(
-        letters in 0i32..20,
-        uppercase in 0i32..20,
-        symbols in 0i32..20,
-        numbers in 0i32..20,
-        seed in any::<u64>(),
-    ) {
-        let mut rng = StdRng::seed_from_u64(seed);
-        let password = generate_password(letters, uppercase, symbols, numbers, &mut rng);
-        for ch in password.chars() {
-            let is_lower = ch.is_ascii_lowercase();
-            let is_upper = ch.is_ascii_uppercase();
-            let is_digit = ch.is_ascii_digit();
-            let is_symbol = SYMBOLS.contains(&(ch as u8));
-            prop_assert!(is_lower || is_upper || is_digit || is_symbol);
+#[test]
+fn remove_sequences_keeps_length_and_classes() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut password = chars("abc123XYZ");
+    let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+    remove_sequences(&mut password, &[], &pools, false, &mut rng);
+    assert_eq!(find_sequence(&password), None);
+    assert_eq!(password.len(), 9);
+    assert_eq!(
+        password.iter().filter(|ch| ch.is_ascii_lowercase()).count(),
+        3
+    );
+    assert_eq!(password.iter().filter(|ch| ch.is_ascii_digit()).count(), 3);
+    assert_eq!(
+        password.iter().filter(|ch| ch.is_ascii_uppercase()).count(),
+        3
+    );
+}
+
+#[test]
+fn app_with_no_sequences_never_emits_runs() {
+    let config = Config::from_args(["--no-sequences".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.numbers = 20;
+    for _ in 0..50 {
+        app.generate_password();
+        assert_eq!(find_sequence(&chars(&app.password)), None);
+    }
+}
+
+#[test]
+fn pronounceable_alternates_consonants_and_vowels() {
+    let mut rng = StdRng::seed_from_u64(21);
+    let password = generate_pronounceable(5, &mut rng);
+    assert_eq!(password.len(), 10);
+    for (index, ch) in password.bytes().enumerate() {
+        if index % 2 == 0 {
+            assert!(CONSONANTS.contains(&ch));
+        } else {
+            assert!(VOWELS.contains(&ch));
+        }
+    }
+}
+
+#[test]
+fn pronounceable_password_appends_symbols_and_numbers() {
+    let mut rng = StdRng::seed_from_u64(23);
+    let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+    let password = generate_pronounceable_password(4, 2, 1, 3, &pools, &mut rng);
+    assert_eq!(password.len(), 12);
+    assert_eq!(
+        password
+            .chars()
+            .filter(|ch| ch.is_ascii_uppercase())
+            .count(),
+        2
+    );
+    assert!(SYMBOLS.contains(&password.as_bytes()[8]));
+    assert!(password[9..].chars().all(|ch| ch.is_ascii_digit()));
+}
+
+#[test]
+fn pronounceable_entropy_uses_reduced_alphabet() {
+    let mut app = App::new(&Config::default());
+    app.letters = 4;
+    app.uppercase = 0;
+    app.symbols = 0;
+    app.numbers = 0;
+    let random_bits = estimate_entropy(&app);
+    assert!((random_bits - 4.0 * 26f64.log2()).abs() < 1e-9);
+
+    app.mode = Mode::Pronounceable;
+    let expected = 4.0 * ((CONSONANTS.len() as f64).log2() + (VOWELS.len() as f64).log2());
+    assert!((estimate_entropy(&app) - expected).abs() < 1e-9);
+}
+
+#[test]
+fn presets_set_documented_fields() {
+    let mut app = App::new(&Config::default());
+
+    app.apply_preset(Preset::Pin);
+    assert_eq!(
+        (app.letters, app.uppercase, app.symbols, app.numbers),
+        (0, 0, 0, 4)
+    );
+    assert!(!app.no_ambiguous);
+
+    app.apply_preset(Preset::Wifi);
+    assert_eq!(total_length(&app), 20);
+    assert!(app.no_ambiguous);
+
+    app.apply_preset(Preset::Nist);
+    assert!(total_length(&app) >= 12);
+    assert!(app.letters > 0 && app.uppercase > 0 && app.symbols > 0 && app.numbers > 0);
+
+    app.apply_preset(Preset::Max);
+    assert_eq!(total_length(&app), 64);
+    assert_eq!(app.preset, Some(Preset::Max));
+}
+
+#[test]
+fn preset_cycle_wraps_around() {
+    assert_eq!(Preset::next(None), Preset::Pin);
+    assert_eq!(Preset::next(Some(Preset::Pin)), Preset::Wifi);
+    assert_eq!(Preset::next(Some(Preset::Max)), Preset::Pin);
+}
+
+#[test]
+fn no_ambiguous_excludes_lookalikes() {
+    let mut app = App::new(&Config::default());
+    app.apply_preset(Preset::Wifi);
+    for _ in 0..50 {
+        app.generate_password();
+        assert!(app.password.chars().all(|ch| !AMBIGUOUS.contains(&ch)));
+    }
+}
+
+#[test]
+fn ui_renders_without_panicking() {
+    let backend = ratatui::backend::TestBackend::new(100, 40);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let app = App::new(&Config::default());
+    terminal.draw(|frame| ui(frame, &app)).unwrap();
+}
+
+#[test]
+fn compact_layout_fits_small_terminal() {
+    let backend = ratatui::backend::TestBackend::new(80, 16);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let app = App::new(&Config::default());
+    terminal.draw(|frame| ui(frame, &app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let rows: Vec<String> = (0..buffer.area.height)
+        .map(|y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect()
+        })
+        .collect();
+    let screen = rows.join("\n");
+    assert!(screen.contains("L6 U2 S2 N4  = 14"));
+    assert!(screen.contains("> Generate"));
+    assert!(screen.contains(&app.password));
+    assert!(rows[14].contains(env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn run_mode_falls_back_to_print_without_terminal() {
+    assert_eq!(select_run_mode(true, false), RunMode::Tui);
+    assert_eq!(select_run_mode(false, false), RunMode::Print);
+    assert_eq!(select_run_mode(true, true), RunMode::Print);
+}
+
+#[test]
+fn clear_countdown_rounds_up_partial_seconds() {
+    assert_eq!(
+        format_clear_countdown(Duration::from_secs(15)),
+        "Clipboard clears in 15s"
+    );
+    assert_eq!(
+        format_clear_countdown(Duration::from_millis(14_200)),
+        "Clipboard clears in 15s"
+    );
+    assert_eq!(
+        format_clear_countdown(Duration::from_millis(300)),
+        "Clipboard clears in 1s"
+    );
+    assert_eq!(
+        format_clear_countdown(Duration::ZERO),
+        "Clipboard clears in 0s"
+    );
+}
+
+#[test]
+fn status_line_composes_transient_status_and_countdown() {
+    let mut app = App::new(&Config::default());
+    assert_eq!(app.status_line(), "");
+
+    app.status = "Copied to clipboard.".to_string();
+    assert_eq!(app.status_line(), "Copied to clipboard.");
+
+    app.clipboard_clear_at = Some(Instant::now() + Duration::from_secs(30));
+    let line = app.status_line();
+    assert!(line.starts_with("Copied to clipboard.  Clipboard clears in "));
+
+    app.status.clear();
+    assert!(app.status_line().starts_with("Clipboard clears in "));
+}
+
+#[test]
+fn log_line_includes_label_and_password() {
+    assert_eq!(
+        format_log_line("example.com", "Ab1!xyz", false),
+        "example.com: Ab1!xyz"
+    );
+    assert_eq!(format_log_line("", "Ab1!xyz", false), "Ab1!xyz");
+}
+
+#[test]
+fn log_line_masks_password_when_requested() {
+    assert_eq!(
+        format_log_line("example.com", "Ab1!xyz", true),
+        "example.com: *******"
+    );
+}
+
+#[test]
+fn metadata_includes_site_label() {
+    assert_eq!(
+        format_metadata("example.com", "pw", None),
+        "Site: example.com\nPassword: pw"
+    );
+    assert_eq!(format_metadata("", "pw", None), "pw");
+    assert_eq!(
+        format_metadata("", "pw", Some("Classes: x")),
+        "pw\nClasses: x"
+    );
+}
+
+#[test]
+fn append_log_appends_lines() {
+    let path = std::env::temp_dir().join(format!("genpass-log-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    append_log(&path, "a: 1").unwrap();
+    append_log(&path, "b: 2").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a: 1\nb: 2\n");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        append_log(&path, "c: 3").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn clipboard_errors_map_to_distinct_messages() {
+    let messages = [
+        ClipboardError::NoBackend.to_string(),
+        ClipboardError::SpawnFailed(io::Error::other("boom")).to_string(),
+        ClipboardError::WriteFailed.to_string(),
+        ClipboardError::Unsupported.to_string(),
+    ];
+    assert!(messages[0].contains("wl-copy"));
+    assert!(messages[1].contains("boom"));
+    for (index, message) in messages.iter().enumerate() {
+        assert!(!messages[..index].contains(message));
+    }
+}
+
+#[test]
+fn count_prefix_applies_twelve_clamped() {
+    let mut count = CountPrefix::default();
+    assert!(count.push_digit(1));
+    assert!(count.push_digit(2));
+
+    let mut app = App::new(&Config::default());
+    app.focus = 1;
+    app.update_value(count.take() as i32);
+    assert_eq!(app.uppercase, DEFAULT_UPPERCASE + 12);
+    assert_eq!(count.take(), 1);
+
+    for digit in [9, 9, 9] {
+        count.push_digit(digit);
+    }
+    app.update_value(count.take() as i32);
+    assert_eq!(app.uppercase, DEFAULT_MAX_VALUE);
+}
+
+#[test]
+fn count_prefix_ignores_leading_zero_and_caps() {
+    let mut count = CountPrefix::default();
+    assert!(!count.push_digit(0));
+    assert_eq!(count.take(), 1);
+
+    for _ in 0..8 {
+        count.push_digit(9);
+    }
+    assert_eq!(count.take(), MAX_COUNT_PREFIX);
+}
+
+#[test]
+fn field_edit_sets_value_on_commit() {
+    let mut app = App::new(&Config::default());
+    app.focus = 2;
+    app.begin_field_edit();
+    assert!(app.push_field_edit('1'));
+    assert!(app.push_field_edit('7'));
+    assert!(!app.push_field_edit('x'));
+    app.commit_field_edit();
+    assert_eq!(app.field_edit, None);
+    assert_eq!(app.symbols, 17);
+}
+
+#[test]
+fn field_edit_clamps_out_of_range_input() {
+    let mut app = App::new(&Config::default());
+    app.focus = 0;
+    app.begin_field_edit();
+    for ch in "999".chars() {
+        app.push_field_edit(ch);
+    }
+    app.commit_field_edit();
+    assert_eq!(app.letters, DEFAULT_MAX_VALUE);
+}
+
+#[test]
+fn field_edit_cancel_and_actions_leave_values_alone() {
+    let mut app = App::new(&Config::default());
+    app.focus = FOCUS_GENERATE;
+    app.begin_field_edit();
+    assert_eq!(app.field_edit, None);
+
+    app.focus = 3;
+    app.begin_field_edit();
+    app.push_field_edit('9');
+    app.field_edit = None;
+    app.commit_field_edit();
+    assert_eq!(app.numbers, DEFAULT_NUMBERS);
+
+    app.begin_field_edit();
+    app.commit_field_edit();
+    assert_eq!(app.numbers, DEFAULT_NUMBERS);
+}
+
+#[test]
+fn shell_safe_output_has_no_unsafe_characters() {
+    let config = Config::from_args(["--shell-safe".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.symbols = 20;
+    for _ in 0..50 {
+        app.generate_password();
+        assert!(app.password.chars().all(|ch| !SHELL_UNSAFE.contains(&ch)));
+        assert_eq!(app.password.len(), total_length(&app) as usize);
+    }
+
+    app.mode = Mode::Pronounceable;
+    app.generate_password();
+    assert!(app.password.chars().all(|ch| !SHELL_UNSAFE.contains(&ch)));
+}
+
+#[test]
+fn shell_safe_keeps_some_symbols() {
+    let remaining = filtered_pool(&chars_of(SYMBOLS), SHELL_UNSAFE);
+    assert_eq!(remaining, chars("#%+"));
+}
+
+#[test]
+fn strength_levels_are_ordered() {
+    assert!(Strength::DoNotUse < Strength::Weak);
+    assert!(Strength::Weak < Strength::Moderate);
+    assert!(Strength::Moderate < Strength::Strong);
+    assert_eq!(Strength::parse("STRONG"), Some(Strength::Strong));
+    assert_eq!(Strength::parse("bogus"), None);
+}
+
+#[test]
+fn min_strength_strong_is_met_with_adequate_counts() {
+    let config = Config::from_args(["--min-strength".to_string(), "strong".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.letters = 2;
+    app.uppercase = 1;
+    app.symbols = 1;
+    app.numbers = 6;
+    for _ in 0..50 {
+        app.generate_password();
+        assert_eq!(app.strength, Strength::Strong);
+        assert!(app.status.is_empty());
+    }
+}
+
+#[test]
+fn unreachable_min_strength_keeps_best_attempt_and_warns() {
+    let config = Config::from_args(["--min-strength".to_string(), "strong".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.letters = 4;
+    app.uppercase = 0;
+    app.symbols = 0;
+    app.numbers = 0;
+    app.generate_password();
+    assert_eq!(app.password.len(), 4);
+    assert!(app.status.contains("Strong"));
+}
+
+#[test]
+fn qr_matrix_is_generated_for_sample_password() {
+    let lines = render_qr("Ab1!cdEF2#").unwrap();
+    assert!(!lines.is_empty());
+    let width = lines[0].chars().count();
+    assert!(width > QR_QUIET_ZONE * 2);
+    assert!(lines.iter().all(|line| line.chars().count() == width));
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.contains(' ') || line.contains('▄'))
+    );
+}
+
+#[test]
+fn qr_rejects_overlong_passwords() {
+    assert!(render_qr(&"a".repeat(1000)).is_err());
+}
+
+#[test]
+fn ui_renders_qr_view() {
+    let backend = ratatui::backend::TestBackend::new(100, 60);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new(&Config::default());
+    app.show_qr = true;
+    terminal.draw(|frame| ui(frame, &app)).unwrap();
+}
+
+#[test]
+fn group_splits_into_groups() {
+    assert_eq!(group("123456789", 3, ' '), "123 456 789");
+    assert_eq!(group("12345678", 4, ' '), "1234 5678");
+    assert_eq!(group("12345", 4, ' '), "1234 5");
+    assert_eq!(group("", 4, ' '), "");
+    assert_eq!(group("a8Kx2mQp9", 3, '-'), "a8K-x2m-Qp9");
+    assert_eq!(group("a8Kx2mQp", 3, '-'), "a8K-x2m-Qp");
+}
+
+#[test]
+fn grouped_display_copies_ungrouped_unless_asked() {
+    let mut app = App::new(&Config::default());
+    app.password = "a8Kx2mQp9".to_string();
+    app.cycle_group_size();
+    assert_eq!(app.group_size, Some(3));
+    assert_eq!(app.display_password(), "a8K-x2m-Qp9");
+    assert_eq!(app.copy_payload(), "a8Kx2mQp9");
+    app.copy_grouped = true;
+    assert_eq!(app.copy_payload(), "a8K-x2m-Qp9");
+
+    app.cycle_group_size();
+    app.cycle_group_size();
+    app.cycle_group_size();
+    assert_eq!(app.group_size, None);
+}
+
+#[test]
+fn pin_mode_generates_digits_and_copies_without_spaces() {
+    let config = Config::from_args(["--pin".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.focus = 0;
+    app.update_value(5);
+    assert_eq!(app.pin_digits, 9);
+    app.update_value(100);
+    assert_eq!(app.pin_digits, PIN_MAX_DIGITS);
+    app.update_value(-100);
+    assert_eq!(app.pin_digits, PIN_MIN_DIGITS);
+
+    app.pin_digits = 9;
+    app.generate_password();
+    assert_eq!(app.password.len(), 9);
+    assert!(app.password.chars().all(|ch| ch.is_ascii_digit()));
+    assert!(app.display_password().contains(' '));
+    assert!(!app.copy_payload().contains(' '));
+    assert_eq!(app.copy_payload(), app.password);
+    app.copy_grouped = true;
+    assert_eq!(app.copy_payload(), app.password);
+}
+
+#[test]
+fn pronounceable_clamp_counts_syllables_as_two_letters() {
+    let args = [
+        "--pronounceable",
+        "--max",
+        "4096",
+        "--symbols",
+        "100",
+        "--numbers",
+        "0",
+    ];
+    let mut app = App::new(&Config::from_args(args.map(String::from)).unwrap());
+    app.focus = 0;
+    app.update_value(MAX_TOTAL_LENGTH);
+    assert_eq!(app.letters, (MAX_TOTAL_LENGTH - 100) / 2);
+    assert_eq!(total_length(&app), MAX_TOTAL_LENGTH);
+    app.generate_password();
+    assert_eq!(app.password.chars().count() as i32, total_length(&app));
+
+    // uppercase recases syllable letters, so it takes no room of its own
+    app.focus = 1;
+    app.update_value(10);
+    assert_eq!(app.uppercase, 12);
+    app.focus = 2;
+    app.update_value(1);
+    assert_eq!(app.symbols, 100);
+}
+
+#[test]
+fn pin_mode_focus_skips_hidden_fields() {
+    let config = Config::from_args(["--pin".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.focus = 0;
+    app.move_focus(1);
+    assert_eq!(app.focus, FOCUS_GENERATE);
+    app.move_focus(-1);
+    assert_eq!(app.focus, 0);
+    app.move_focus(-1);
+    assert_eq!(app.focus, 0);
+    app.move_focus(10);
+    assert_eq!(app.focus, FOCUS_QUIT);
+}
+
+#[test]
+fn split_letters_handles_edge_ratios() {
+    assert_eq!(split_letters(10, 0), (10, 0));
+    assert_eq!(split_letters(10, 50), (5, 5));
+    assert_eq!(split_letters(10, 100), (0, 10));
+    assert_eq!(split_letters(7, 50), (3, 4));
+    assert_eq!(split_letters(9, 33), (6, 3));
+    assert_eq!(split_letters(0, 50), (0, 0));
+}
+
+#[test]
+fn case_ratio_layout_adjusts_total_and_percentage() {
+    let config = Config::from_args(["--upper-percent".to_string(), "50".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    assert_eq!(
+        app.letters + app.uppercase,
+        DEFAULT_LETTERS + DEFAULT_UPPERCASE
+    );
+    assert_eq!(app.letters, app.uppercase);
+
+    app.focus = 0;
+    app.update_value(2);
+    assert_eq!((app.letters, app.uppercase), (5, 5));
+
+    app.focus = 1;
+    app.update_value(50);
+    assert_eq!((app.letters, app.uppercase), (0, 10));
+    assert_eq!(app.field_value(), Some(100));
+
+    app.toggle_case_ratio();
+    assert_eq!(app.field_value(), Some(10));
+    app.toggle_case_ratio();
+    assert_eq!(app.upper_pct, 100);
+}
+
+#[test]
+fn compare_pin_and_swap_transitions() {
+    let mut app = App::new(&Config::default());
+    app.swap_compare();
+    assert!(!app.compare_pinned_active);
+
+    let first = app.password.clone();
+    app.pin_compare();
+    assert_eq!(app.compare_slot.as_deref(), Some(first.as_str()));
+    assert!(!app.compare_pinned_active);
+
+    app.password = "Current1!".to_string();
+    assert_eq!(app.copy_payload(), "Current1!");
+
+    app.swap_compare();
+    assert!(app.compare_pinned_active);
+    assert_eq!(app.copy_payload(), first);
+
+    app.swap_compare();
+    assert_eq!(app.copy_payload(), "Current1!");
+
+    app.swap_compare();
+    app.clear_compare();
+    assert_eq!(app.compare_slot, None);
+    assert_eq!(app.copy_payload(), "Current1!");
+}
+
+#[test]
+fn ui_renders_compare_view() {
+    let backend = ratatui::backend::TestBackend::new(100, 40);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new(&Config::default());
+    app.pin_compare();
+    app.generate_password();
+    terminal.draw(|frame| ui(frame, &app)).unwrap();
+}
+
+#[test]
+fn count_flags_set_initial_fields() {
+    let args = [
+        "--letters",
+        "3",
+        "--uppercase",
+        "0",
+        "--symbols",
+        "1",
+        "--numbers",
+        "7",
+    ];
+    let config = Config::from_args(args.map(String::from)).unwrap();
+    let app = App::new(&config);
+    assert_eq!(
+        (app.letters, app.uppercase, app.symbols, app.numbers),
+        (3, 0, 1, 7)
+    );
+    assert!(Config::from_args(["--letters", "-1"].map(String::from)).is_err());
+}
+
+#[test]
+fn unicode_symbol_set_generates_multibyte_chars() {
+    let config = Config::from_args(["--symbol-set".to_string(), "•—€".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.letters = 3;
+    app.uppercase = 2;
+    app.symbols = 5;
+    app.numbers = 2;
+    for _ in 0..20 {
+        app.generate_password();
+        assert_eq!(app.password.chars().count(), 12);
+        assert_eq!(
+            app.password
+                .chars()
+                .filter(|ch| "•—€".contains(*ch))
+                .count(),
+            5
+        );
+        assert!(app.password.len() > 12);
+        assert_eq!(app.strength, Strength::Strong);
+    }
+    let expected = 3.0 * 26f64.log2() + 2.0 * 26f64.log2() + 5.0 * 3f64.log2() + 2.0 * 10f64.log2();
+    assert!((estimate_entropy(&app) - expected).abs() < 1e-9);
+}
+
+#[test]
+fn unicode_symbol_set_never_yields_empty_password() {
+    let config = Config::from_args(["--symbol-set".to_string(), "•—€£¥".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    (app.letters, app.uppercase, app.symbols, app.numbers) = (0, 0, 4, 0);
+    for mode in [Mode::Random, Mode::Pronounceable] {
+        app.mode = mode;
+        for _ in 0..20 {
+            app.generate_password();
+            assert!(!app.password.is_empty());
+            assert!(app.password.chars().all(|ch| "•—€£¥".contains(ch)));
         }
     }
 }
+
+#[test]
+fn copy_safe_rejects_control_and_non_ascii() {
+    assert!(is_copy_safe("Tr0ub4dor&3 ~"));
+    assert!(!is_copy_safe("abc\u{7}"));
+    assert!(!is_copy_safe("tab\there"));
+    assert!(!is_copy_safe("del\u{7f}"));
+    assert!(!is_copy_safe("€uro"));
+}
+
+#[test]
+fn printable_only_regenerates_and_reports_failure() {
+    let mut app = App::new(&Config::default());
+    assert!(app.ascii_printable_only);
+    app.symbol_set = chars("!€");
+    (app.letters, app.uppercase, app.symbols, app.numbers) = (4, 4, 1, 4);
+    for _ in 0..20 {
+        app.generate_password();
+        assert!(is_copy_safe(&app.password));
+        assert!(!app.unsafe_chars);
+    }
+
+    app.symbol_set = chars("€");
+    app.generate_password();
+    assert!(app.unsafe_chars);
+    assert_eq!(
+        app.status,
+        "Printable-only is on, but € is not ASCII; press U or pass --allow-unicode."
+    );
+
+    app.toggle_printable_only();
+    app.generate_password();
+    assert!(!app.unsafe_chars);
+    assert!(app.password.contains('€'));
+}
+
+#[test]
+fn unicode_symbol_edit_warns_while_printable_only() {
+    let mut app = App::new(&Config::default());
+    app.symbol_edit = Some(TextInput::new("!€"));
+    app.commit_symbol_edit();
+    assert_eq!(app.symbol_set, ['!', '€']);
+    assert_eq!(
+        app.status,
+        "€ is not printable ASCII; press U to allow Unicode symbols."
+    );
+
+    app.status.clear();
+    app.toggle_printable_only();
+    app.symbol_edit = Some(TextInput::new("!€"));
+    app.commit_symbol_edit();
+    assert_eq!(app.status, "Unicode symbols allowed.");
+}
+
+#[test]
+fn strength_counts_chars_not_bytes() {
+    assert_eq!(
+        check_password_strength("Aa1€", STRENGTH_MIN_LENGTH),
+        Strength::Moderate
+    );
+    assert_eq!(
+        check_password_strength("Aa1€€€€€€€", STRENGTH_MIN_LENGTH),
+        Strength::Strong
+    );
+}
+
+#[test]
+fn symbol_set_validation_rejects_letters_and_whitespace() {
+    assert_eq!(sanitize_symbol_set("•—€•").unwrap(), chars("•—€"));
+    assert_eq!(sanitize_symbol_set(""), Err(SymbolSetError::Empty));
+    assert_eq!(
+        sanitize_symbol_set("!a"),
+        Err(SymbolSetError::NotAllowed('a'))
+    );
+    assert!(sanitize_symbol_set("! ").is_err());
+    assert!(sanitize_symbol_set("!\t").is_err());
+    assert!(Config::from_args(["--symbol-set".to_string(), "abc".to_string()]).is_err());
+}
+
+#[test]
+fn symbol_edit_commit_validates_input() {
+    let mut app = App::new(&Config::default());
+    app.symbol_edit = Some(TextInput::new("€£"));
+    app.commit_symbol_edit();
+    assert_eq!(app.symbol_set, chars("€£"));
+
+    app.symbol_edit = Some(TextInput::new("x"));
+    app.commit_symbol_edit();
+    assert_eq!(app.symbol_set, chars("€£"));
+    assert!(!app.status.is_empty());
+}
+
+#[test]
+fn leading_letter_output_starts_with_letter() {
+    let config = Config::from_args(["--leading-letter".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.letters = 1;
+    app.uppercase = 0;
+    app.symbols = 10;
+    app.numbers = 10;
+    for _ in 0..100 {
+        app.generate_password();
+        assert!(app.password.chars().next().unwrap().is_ascii_alphabetic());
+    }
+
+    app.no_sequences = true;
+    app.uppercase = 3;
+    for _ in 0..100 {
+        app.generate_password();
+        assert!(app.password.chars().next().unwrap().is_ascii_alphabetic());
+        assert_eq!(find_sequence(&chars(&app.password)), None);
+    }
+}
+
+#[test]
+fn leading_letter_without_letters_warns() {
+    let mut password = chars("12#4");
+    assert!(!ensure_leading_letter(&mut password, &[]));
+    assert_eq!(password, chars("12#4"));
+
+    let config = Config::from_args(["--leading-letter".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.letters = 0;
+    app.uppercase = 0;
+    app.generate_password();
+    assert!(app.status.contains("Leading letter"));
+}
+
+#[test]
+fn diff_mask_marks_changed_characters() {
+    assert_eq!(diff_mask("abcd", "abXd"), vec![false, false, true, false]);
+    assert_eq!(diff_mask("abcd", "abcd"), vec![false; 4]);
+    assert_eq!(diff_mask("ab", "abcd"), vec![false, false, true, true]);
+    assert_eq!(diff_mask("abcd", "aX"), vec![false, true]);
+    assert_eq!(diff_mask("", "ab"), vec![true, true]);
+}
+
+#[test]
+fn regenerating_records_previous_password_and_flash() {
+    let mut app = App::new(&Config::default());
+    assert!(app.flash_until.is_none());
+    let first = app.password.clone();
+    app.generate_password();
+    assert_eq!(app.previous_password, first);
+    assert!(app.flash_until.is_some());
+    assert_eq!(
+        app.password_spans(&palette(ThemeKind::Dark)).len(),
+        app.password.chars().count()
+    );
+
+    app.flash_until = Some(Instant::now() - Duration::from_millis(1));
+    assert_eq!(app.password_spans(&palette(ThemeKind::Dark)).len(), 1);
+}
+
+#[test]
+fn check_report_lists_strength_entropy_and_criteria() {
+    let report = format_check_report("hunter2", None, STRENGTH_MIN_LENGTH);
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "Strength: Do not use!!!!");
+    assert_eq!(lines[1], format!("Entropy: {:.1} bits", 7.0 * 36f64.log2()));
+    assert_eq!(
+        &lines[2..],
+        [
+            "Length (10+): no",
+            "Uppercase: no",
+            "Lowercase: yes",
+            "Number: yes",
+            "Symbol: no"
+        ]
+    );
+}
+
+#[test]
+fn check_rejects_empty_input() {
+    assert_eq!(
+        run_check(&mut "\n".as_bytes(), false, STRENGTH_MIN_LENGTH),
+        2
+    );
+    assert_eq!(run_check(&mut "".as_bytes(), false, STRENGTH_MIN_LENGTH), 2);
+}
+
+#[test]
+fn next_focus_wraps_in_both_directions() {
+    assert_eq!(next_focus(0, 1), 1);
+    assert_eq!(next_focus(FOCUS_QUIT, 1), 0);
+    assert_eq!(next_focus(0, -1), FOCUS_QUIT);
+    assert_eq!(next_focus(FOCUS_GENERATE, -1), FOCUS_FIELDS - 1);
+}
+
+#[test]
+fn cycle_focus_skips_hidden_fields_in_pin_mode() {
+    let config = Config::from_args(["--pin".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.focus = 0;
+    app.cycle_focus(1);
+    assert_eq!(app.focus, FOCUS_GENERATE);
+    app.cycle_focus(-1);
+    assert_eq!(app.focus, 0);
+    app.cycle_focus(-1);
+    assert_eq!(app.focus, FOCUS_QUIT);
+}
+
+#[test]
+fn theme_palettes_differ() {
+    let dark = palette(ThemeKind::Dark);
+    let light = palette(ThemeKind::Light);
+    let high_contrast = palette(ThemeKind::HighContrast);
+    assert_ne!(dark, light);
+    assert_ne!(dark, high_contrast);
+    assert_ne!(light, high_contrast);
+}
+
+#[test]
+fn high_contrast_uses_only_standard_colors() {
+    let theme = palette(ThemeKind::HighContrast);
+    let colors = [
+        theme.active,
+        theme.inactive,
+        theme.good,
+        theme.warn,
+        theme.bad,
+        theme.status,
+        theme.flash,
+        theme.lowercase,
+        theme.uppercase,
+        theme.digit,
+        theme.symbol,
+    ];
+    for color in colors {
+        assert!(
+            !matches!(color, Color::Rgb(..) | Color::Indexed(_) | Color::Reset),
+            "{color:?} is not one of the 16 standard colors"
+        );
+    }
+}
+
+#[test]
+fn theme_setting_round_trips() {
+    let path = std::env::temp_dir()
+        .join(format!("genpass-theme-{}", std::process::id()))
+        .join("settings.toml");
+    save_theme(&path, ThemeKind::HighContrast).unwrap();
+    assert_eq!(load_theme(&path), Some(ThemeKind::HighContrast));
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+    assert_eq!(ThemeKind::Dark.next().next().next(), ThemeKind::Dark);
+    assert!(Config::from_args(["--theme".to_string(), "neon".to_string()]).is_err());
+}
+
+#[test]
+fn distribute_sums_to_total() {
+    for total in [0, 1, 3, 4, 5, 7, 10, 16, 33, 100, MAX_TOTAL_LENGTH] {
+        let (letters, uppercase, symbols, numbers) = distribute(total);
+        assert_eq!(
+            letters + uppercase + symbols + numbers,
+            total,
+            "total {total}"
+        );
+    }
+    assert_eq!(distribute(20), (10, 3, 3, 4));
+}
+
+#[test]
+fn distribute_keeps_every_class_from_four() {
+    for total in 4..=64 {
+        let (letters, uppercase, symbols, numbers) = distribute(total);
+        assert!(
+            letters >= 1 && uppercase >= 1 && symbols >= 1 && numbers >= 1,
+            "total {total}"
+        );
+    }
+}
+
+#[test]
+fn length_only_mode_adjusts_total() {
+    let config = Config::from_args(["--length".to_string(), "16".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    assert_eq!(app.password.chars().count(), 16);
+    assert!(!app.field_visible(1));
+    app.update_value(4);
+    assert_eq!(total_length(&app), 20);
+    assert_eq!(app.field_value(), Some(20));
+    assert!(Config::from_args(["--length".to_string(), "-1".to_string()]).is_err());
+}
+
+#[test]
+fn warns_only_for_weak_strengths_when_enabled() {
+    assert!(should_warn(Strength::Weak, true));
+    assert!(should_warn(Strength::DoNotUse, true));
+    assert!(!should_warn(Strength::Moderate, true));
+    assert!(!should_warn(Strength::Strong, true));
+    assert!(!should_warn(Strength::Weak, false));
+}
+
+#[test]
+fn batch_copy_selects_row() {
+    let mut app = App::new(&Config::default());
+    app.copy_batch_row(1);
+    assert_eq!(app.batch_selected, 0);
+    app.toggle_batch();
+    assert_eq!(app.batch.as_ref().unwrap().len(), BATCH_SIZE);
+    app.copy_batch_row(1);
+    assert_eq!(app.batch_selected, 1);
+    app.copy_batch_row(BATCH_SIZE);
+    assert_eq!(app.batch_selected, 1);
+    app.toggle_batch();
+    assert!(app.batch.is_none());
+}
+
+#[test]
+fn batch_copy_logs_the_copied_row() {
+    let path = std::env::temp_dir().join(format!("genpass-batch-log-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut app = App::new(&Config::default());
+    app.log_path = Some(path.clone());
+    app.toggle_batch();
+    let row = app.batch.as_ref().unwrap()[1].password.clone();
+    assert_ne!(row, app.password);
+    take_copies();
+    app.copy_batch_row(1);
+    assert_eq!(take_copies(), [row.as_str()]);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{row}\n"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn metadata_copy_logs_only_the_password() {
+    let path = std::env::temp_dir().join(format!("genpass-meta-log-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut app = App::new(&Config::default());
+    app.log_path = Some(path.clone());
+    app.label = "example.com".to_string();
+    app.meta_breakdown = true;
+    take_copies();
+    app.copy_with_metadata();
+    assert!(take_copies()[0].contains("Entropy:"));
+    let logged = format!("example.com: {}\n", app.password);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), logged);
+
+    app.mask_log = true;
+    app.copy_with_metadata();
+    let masked = format!(
+        "example.com: {}\n",
+        "*".repeat(app.password.chars().count())
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), logged + &masked);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn metadata_copy_follows_the_active_password() {
+    let mut app = App::new(&Config::default());
+    app.label = "example.com".to_string();
+    app.pin_compare();
+    let pinned = app.password.clone();
+    app.generate_password();
+    app.swap_compare();
+    take_copies();
+    app.copy_with_metadata();
+    assert_eq!(
+        take_copies(),
+        [format!("Site: example.com\nPassword: {pinned}")]
+    );
+    app.copy_password();
+    assert_eq!(take_copies(), [pinned]);
+}
+
+#[test]
+fn strength_criteria_matches_samples() {
+    let names = ["Length (10+)", "Uppercase", "Lowercase", "Number", "Symbol"];
+    let cases = [
+        ("abc", [false, false, true, false, false]),
+        ("ABCDEFGHIJ", [true, true, false, false, false]),
+        ("Abc123!", [false, true, true, true, true]),
+        ("Tr0ub4dor&3x", [true, true, true, true, true]),
+        ("", [false; 5]),
+    ];
+    for (password, expected) in cases {
+        let criteria = strength_criteria(password, STRENGTH_MIN_LENGTH);
+        for ((name, met), (expected_name, expected_met)) in
+            criteria.iter().zip(names.iter().zip(expected))
+        {
+            assert_eq!(name, expected_name);
+            assert_eq!(*met, expected_met, "{password:?} {name}");
+        }
+    }
+}
+
+#[test]
+fn ui_renders_breakdown_panel() {
+    let backend = ratatui::backend::TestBackend::new(100, 50);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new(&Config::default());
+    app.show_breakdown = true;
+    terminal.draw(|frame| ui(frame, &app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+    assert!(text.contains("Breakdown"));
+}
+
+#[test]
+fn ui_renders_phonetic_panel_without_spare_rows() {
+    // the full layout with the breakdown leaves no filler rows at this height
+    let backend = ratatui::backend::TestBackend::new(100, COMPACT_HEIGHT + 4);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new(&Config::default());
+    app.password = "a1".to_string();
+    app.show_phonetic = true;
+    app.show_breakdown = true;
+    terminal.draw(|frame| ui(frame, &app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+    assert!(text.contains("Phonetic"));
+    assert!(text.contains(&phonetic("a1").join(" ")));
+}
+
+#[test]
+fn accel_step_ramps_up() {
+    assert_eq!(accel_step(0), 1);
+    assert_eq!(accel_step(4), 1);
+    assert_eq!(accel_step(5), 2);
+    assert_eq!(accel_step(10), 5);
+    assert_eq!(accel_step(20), 10);
+    assert_eq!(accel_step(u32::MAX), 10);
+}
+
+#[test]
+fn hold_step_resets_on_pause_or_direction_change() {
+    let mut app = App::new(&Config::default());
+    let start = Instant::now();
+    let tick = Duration::from_millis(30);
+    let steps: Vec<i32> = (0..12)
+        .map(|i| app.hold_step(1, start + tick * i))
+        .collect();
+    assert_eq!(steps[0], 1);
+    assert_eq!(steps[5], 2);
+    assert_eq!(steps[11], 5);
+
+    assert_eq!(app.hold_step(-1, start + tick * 12), 1);
+    assert_eq!(app.hold_step(-1, start + Duration::from_secs(5)), 1);
+}
+
+struct CountingRestore<'a>(&'a std::cell::Cell<u32>);
+
+impl TerminalRestore for CountingRestore<'_> {
+    fn restore(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+fn terminal_guard_restores_on_drop() {
+    let restored = std::cell::Cell::new(0);
+    {
+        let _guard = TerminalGuard {
+            restore: CountingRestore(&restored),
+        };
+        assert_eq!(restored.get(), 0);
+    }
+    assert_eq!(restored.get(), 1);
+}
+
+#[test]
+fn terminal_guard_restores_when_unwinding() {
+    let restored = std::cell::Cell::new(0);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _guard = TerminalGuard {
+            restore: CountingRestore(&restored),
+        };
+        panic!("layout bug");
+    }));
+    assert!(result.is_err());
+    assert_eq!(restored.get(), 1);
+}
+
+#[test]
+fn copy_count_resets_on_regeneration() {
+    let mut app = App::new(&Config::default());
+    app.record_copy();
+    app.record_copy();
+    assert_eq!(app.copy_count, 2);
+    assert_eq!(app.status, "Copied (x2)");
+    assert!(!app.copy_blocked());
+    app.generate_password();
+    assert_eq!(app.copy_count, 0);
+}
+
+#[test]
+fn single_use_blocks_second_copy() {
+    let config = Config::from_args(["--single-use".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    assert!(!app.copy_blocked());
+    app.record_copy();
+    assert!(app.copy_blocked());
+    app.copy_password();
+    assert_eq!(app.copy_count, 1);
+    assert!(app.status.starts_with("Password already used"));
+    app.generate_password();
+    assert!(!app.copy_blocked());
+}
+
+#[test]
+fn parses_spec_env() {
+    assert_eq!(
+        parse_spec_env("l=8,u=2,s=2,n=4"),
+        Ok(PasswordSpec {
+            letters: Some(8),
+            uppercase: Some(2),
+            symbols: Some(2),
+            numbers: Some(4),
+            ..PasswordSpec::default()
+        })
+    );
+    assert_eq!(
+        parse_spec_env(" n = 6 ,"),
+        Ok(PasswordSpec {
+            numbers: Some(6),
+            ..PasswordSpec::default()
+        })
+    );
+    assert_eq!(parse_spec_env(""), Ok(PasswordSpec::default()));
+}
+
+#[test]
+fn rejects_malformed_spec_env() {
+    assert_eq!(
+        parse_spec_env("x=3"),
+        Err(SpecParseError::UnknownKey("x".to_string()))
+    );
+    assert_eq!(
+        parse_spec_env("l=eight"),
+        Err(SpecParseError::InvalidValue(
+            "l".to_string(),
+            "eight".to_string()
+        ))
+    );
+    assert_eq!(
+        parse_spec_env("l=-1"),
+        Err(SpecParseError::InvalidValue(
+            "l".to_string(),
+            "-1".to_string()
+        ))
+    );
+    assert_eq!(
+        parse_spec_env("l8"),
+        Err(SpecParseError::MissingValue("l8".to_string()))
+    );
+}
+
+#[test]
+fn cli_flags_override_spec_env() {
+    let spec = parse_spec_env("l=8,n=1").unwrap();
+    let config = Config::from_args_over(
+        Config::from_spec(&spec),
+        ["--numbers".to_string(), "5".to_string()],
+    )
+    .unwrap();
+    assert_eq!(config.letters, 8);
+    assert_eq!(config.uppercase, DEFAULT_UPPERCASE);
+    assert_eq!(config.numbers, 5);
+}
+
+#[test]
+fn denylist_matches_case_insensitively() {
+    let denylist = vec!["acme".to_string(), "Pass".to_string()];
+    assert!(contains_denied("xxACMExx", &denylist));
+    assert!(contains_denied("mypassword", &denylist));
+    assert!(!contains_denied("acm3", &denylist));
+    assert!(!contains_denied("anything", &[]));
+}
+
+#[test]
+fn loads_denylist_skipping_blank_lines() {
+    let path = std::env::temp_dir().join(format!("genpass-deny-{}", std::process::id()));
+    std::fs::write(&path, "Acme\n\n  corp  \n").unwrap();
+    let denylist = load_denylist(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(denylist, ["acme", "corp"]);
+}
+
+#[test]
+fn denied_content_is_retried_then_reported() {
+    let mut config = Config {
+        letters: 4,
+        uppercase: 0,
+        symbols: 0,
+        numbers: 0,
+        ..Config::default()
+    };
+    config.denylist = vec!["q".to_string()];
+    let app = App::new(&config);
+    assert!(!app.denied);
+    assert!(!app.password.contains('q'));
+
+    config.denylist = ('a'..='z').map(String::from).collect();
+    let app = App::new(&config);
+    assert!(app.denied);
+    assert!(app.status.contains("denied"));
+}
+
+#[test]
+fn spec_entropy_uses_pool_sizes() {
+    let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+    let spec = PasswordSpec {
+        letters: Some(8),
+        uppercase: Some(2),
+        symbols: Some(2),
+        numbers: Some(4),
+        ..PasswordSpec::default()
+    };
+    let expected = 10.0 * 26f64.log2() + 2.0 * 9f64.log2() + 4.0 * 10f64.log2();
+    assert!((spec_entropy_bits(&spec, &pools) - expected).abs() < 1e-9);
+    assert_eq!(spec_entropy_bits(&PasswordSpec::default(), &pools), 0.0);
+
+    let no_ambiguous = Pools::new(&chars_of(SYMBOLS), AMBIGUOUS);
+    assert!(spec_entropy_bits(&spec, &no_ambiguous) < spec_entropy_bits(&spec, &pools));
+}
+
+#[test]
+fn gauge_style_follows_thresholds() {
+    let theme = palette(ThemeKind::Dark);
+    let thresholds = Thresholds::default();
+    assert_eq!(gauge_style(20.0, &thresholds, &theme), (theme.bad, 0.25));
+    assert_eq!(gauge_style(39.9, &thresholds, &theme).0, theme.bad);
+    assert_eq!(gauge_style(40.0, &thresholds, &theme), (theme.warn, 0.5));
+    assert_eq!(gauge_style(59.9, &thresholds, &theme).0, theme.warn);
+    assert_eq!(gauge_style(60.0, &thresholds, &theme).0, theme.good);
+    assert_eq!(gauge_style(90.0, &thresholds, &theme), (theme.good, 1.0));
+
+    let relaxed = Thresholds {
+        warn: 30.0,
+        good: 50.0,
+        full: 64.0,
+    };
+    assert_eq!(
+        gauge_style(50.0, &relaxed, &theme),
+        (theme.good, 50.0 / 64.0)
+    );
+}
+
+#[test]
+fn thresholds_load_from_settings_and_survive_theme_saves() {
+    let path = std::env::temp_dir()
+        .join(format!("genpass-thresholds-{}", std::process::id()))
+        .join("settings.toml");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "gauge_warn_bits = 30\ngauge_good_bits = 50\n").unwrap();
+    save_theme(&path, ThemeKind::Light).unwrap();
+    let loaded = load_thresholds(&path);
+    assert_eq!((loaded.warn, loaded.good, loaded.full), (30.0, 50.0, 80.0));
+    assert_eq!(load_theme(&path), Some(ThemeKind::Light));
+
+    std::fs::write(&path, "gauge_warn_bits = 70\ngauge_good_bits = 50\n").unwrap();
+    assert_eq!(load_thresholds(&path), Thresholds::default());
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn pasted_symbols_keep_only_punctuation() {
+    assert_eq!(sanitize_paste(TextField::Symbols, "a!b@ c#1\n€"), "!@#€");
+    assert_eq!(sanitize_paste(TextField::Symbols, "abc 123"), "");
+    assert_eq!(sanitize_paste(TextField::Count, " 1,024\n"), "1024");
+    assert_eq!(
+        sanitize_paste(TextField::Label, "example.com\r\n"),
+        "example.com"
+    );
+}
+
+#[test]
+fn excluded_characters_never_appear() {
+    let config = Config::from_args(["--exclude".to_string(), "$aA0 $".to_string()]).unwrap();
+    assert_eq!(config.exclude, ['$', 'a', 'A', '0']);
+    let mut app = App::new(&config);
+    for _ in 0..50 {
+        app.generate_password();
+        assert!(
+            !app.password.contains(['$', 'a', 'A', '0']),
+            "{}",
+            app.password
+        );
+    }
+}
+
+#[test]
+fn excluding_all_symbols_zeroes_their_contribution() {
+    let mut app = App::new(&Config::default());
+    let with_symbols = estimate_entropy(&app);
+    app.exclude = chars_of(SYMBOLS);
+    app.generate_password();
+    assert_eq!(
+        app.password.chars().count(),
+        (total_length(&app) - app.symbols) as usize
+    );
+    assert!(app.status.contains("symbols"));
+    let expected = with_symbols - bits(app.symbols, SYMBOLS.len());
+    assert!((estimate_entropy(&app) - expected).abs() < 1e-9);
+}
+
+#[cfg(unix)]
+#[test]
+fn write_secure_restricts_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join(format!("genpass-out-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    write_secure(&path, "secret\n", false).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let err = write_secure(&path, "other\n", false).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "secret\n");
+
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    write_secure(&path, "other\n", true).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "other\n");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn filter_history_matches_substrings() {
+    let history = [
+        "abc123".to_string(),
+        "xyz".to_string(),
+        "123abc".to_string(),
+    ];
+    assert_eq!(filter_history(&history, "abc"), [0, 2]);
+    assert_eq!(filter_history(&history, "ABC"), Vec::<usize>::new());
+    assert_eq!(filter_history(&history, "y"), [1]);
+    assert_eq!(filter_history(&history, ""), [0, 1, 2]);
+}
+
+#[test]
+fn history_is_capped_and_selectable() {
+    let mut app = App::new(&Config::default());
+    for _ in 0..HISTORY_CAP + 5 {
+        app.generate_password();
+    }
+    assert_eq!(app.history.len(), HISTORY_CAP);
+
+    let mut app = App::new(&Config::default());
+    let first = app.password.clone();
+    app.generate_password();
+    let second = app.password.clone();
+    app.history_filter = Some(first.clone());
+    app.select_history();
+    assert_eq!(app.password, first);
+    assert_eq!(app.history[0], second);
+    assert!(app.history_filter.is_none());
+}
+
+#[test]
+fn parses_key_specs() {
+    let key = |code, modifiers| Some(KeyCombo { code, modifiers });
+    assert_eq!(
+        parse_key("ctrl+r"),
+        key(KeyCode::Char('r'), KeyModifiers::CONTROL)
+    );
+    assert_eq!(
+        parse_key("shift+tab"),
+        key(KeyCode::BackTab, KeyModifiers::NONE)
+    );
+    assert_eq!(
+        parse_key("shift+g"),
+        key(KeyCode::Char('G'), KeyModifiers::NONE)
+    );
+    assert_eq!(parse_key("Enter"), key(KeyCode::Enter, KeyModifiers::NONE));
+    assert_eq!(parse_key("+"), key(KeyCode::Char('+'), KeyModifiers::NONE));
+    assert_eq!(
+        parse_key("ctrl++"),
+        key(KeyCode::Char('+'), KeyModifiers::CONTROL)
+    );
+    assert_eq!(parse_key("f5"), key(KeyCode::F(5), KeyModifiers::NONE));
+    assert_eq!(parse_key("hyper+x"), None);
+    assert_eq!(parse_key("nonsense"), None);
+    assert_eq!(parse_key(""), None);
+}
+
+#[test]
+fn keymap_overrides_replace_defaults() {
+    let combo = |spec| parse_key(spec).unwrap();
+    let keymap = parse_keymap(
+        "# dvorak\nfocus_up = [\"up\", \"t\"]\ngenerate = \"ctrl+g\"\nbogus = \"x\"\ncopy = \"nope+x\"\n",
+    );
+    assert_eq!(keymap.get(&combo("t")), Some(&Action::FocusUp));
+    assert_eq!(keymap.get(&combo("k")), None);
+    assert_eq!(keymap.get(&combo("ctrl+g")), Some(&Action::Generate));
+    assert_eq!(keymap.get(&combo("g")), None);
+    assert_eq!(keymap.get(&combo("c")), Some(&Action::Copy));
+    assert_eq!(keymap.get(&combo("q")), Some(&Action::Quit));
+
+    let missing = std::env::temp_dir().join("genpass-no-such-keymap.toml");
+    assert_eq!(load_keymap(&missing), default_keymap());
+    assert_eq!(
+        default_keymap().get(&KeyCombo::new(KeyCode::Char('C'), KeyModifiers::SHIFT)),
+        Some(&Action::Copy)
+    );
+}
+
+#[test]
+fn keymap_accepts_quoted_comma_and_bracket() {
+    let keymap = parse_keymap("copy = [\",\", \"]\", \"\\\"\"]\nquit = \"x\"\n");
+    assert_eq!(keymap.get(&parse_key(",").unwrap()), Some(&Action::Copy));
+    assert_eq!(keymap.get(&parse_key("]").unwrap()), Some(&Action::Copy));
+    assert_eq!(keymap.get(&parse_key("\"").unwrap()), Some(&Action::Copy));
+    assert_eq!(keymap.get(&parse_key("c").unwrap()), None);
+    assert_eq!(parse_key_list("[\"a,b\",\"c\"]"), ["a,b", "c"]);
+}
+
+#[test]
+fn footer_hints_follow_the_keymap() {
+    assert_eq!(key_hints(&default_keymap()), "g generate  c copy  q quit");
+    let keymap = parse_keymap("generate = \"ctrl+g\"\ncopy = [\"y\", \",\"]\n");
+    assert_eq!(key_hints(&keymap), "Ctrl+g generate  , copy  q quit");
+    let config = Config {
+        keymap,
+        ..Config::default()
+    };
+    assert!(
+        footer_text(RngSource::Thread, &App::new(&config).key_hints)
+            .ends_with("Ctrl+g generate  , copy  q quit")
+    );
+}
+
+#[test]
+fn symbol_and_exclude_edits_start_from_current_values() {
+    let mut app = App::new(&Config::default());
+    app.symbol_set = vec!['!', '@'];
+    app.exclude = vec!['0', 'O'];
+    app.open_symbol_edit();
+    let input = app.symbol_edit.as_ref().unwrap();
+    assert_eq!((input.text.as_str(), input.cursor), ("!@", 2));
+    app.open_exclude_edit();
+    assert_eq!(app.exclude_edit.as_ref().unwrap().text, "0O");
+
+    app.commit_symbol_edit();
+    app.commit_exclude_edit();
+    assert_eq!(app.symbol_set, ['!', '@']);
+    assert_eq!(app.exclude, ['0', 'O']);
+}
+
+#[test]
+fn compact_actions_line_shows_the_active_edit() {
+    let text = |line: Line| {
+        line.spans
+            .iter()
+            .map(|span| span.content.to_string())
+            .collect::<String>()
+    };
+    let theme = palette(ThemeKind::Dark);
+    let mut app = App::new(&Config::default());
+    assert!(text(compact_actions_line(&app, &theme)).contains("Generate"));
+
+    app.open_symbol_edit();
+    assert!(text(compact_actions_line(&app, &theme)).starts_with("Symbol set: !#$%&()*+"));
+    app.symbol_edit = None;
+    app.open_exclude_edit();
+    assert!(text(compact_actions_line(&app, &theme)).starts_with("Exclude: "));
+    app.exclude_edit = None;
+    app.label_edit = Some(TextInput::new("bank"));
+    assert!(text(compact_actions_line(&app, &theme)).starts_with("Site: bank"));
+}
+
+#[test]
+fn six_word_diceware_phrase_is_strong() {
+    assert_eq!(passphrase_strength(6, 7776, 0.0), Strength::Strong);
+    assert_eq!(passphrase_strength(4, 7776, 0.0), Strength::Moderate);
+    assert_eq!(passphrase_strength(1, 7776, 0.0), Strength::DoNotUse);
+    assert_eq!(
+        check_password_strength(
+            "correct-horse-battery-staple-lunar-tiger",
+            STRENGTH_MIN_LENGTH
+        ),
+        Strength::Weak
+    );
+}
+
+#[test]
+fn passphrase_mode_scores_by_word_count() {
+    let config = Config::from_args(["--passphrase".to_string()]).unwrap();
+    let app = App::new(&config);
+    let words: Vec<&str> = app.password.split(PASSPHRASE_SEPARATOR).collect();
+    assert_eq!(words.len(), DEFAULT_PASSPHRASE_WORDS as usize);
+    assert!(words.iter().all(|word| wordlist().contains(word)));
+    assert_eq!(app.strength, Strength::Strong);
+    assert_eq!(strength_for_mode(&app, &app.password), Strength::Strong);
+}
+
+#[test]
+fn config_display_lists_resolved_values() {
+    let config = Config::from_args(
+        ["--pin", "--letters", "9", "--exclude", "$"]
+            .into_iter()
+            .map(String::from),
+    )
+    .unwrap();
+    let text = config.to_string();
+    assert!(text.contains("mode: PIN\n"));
+    assert!(text.contains("letters: 9\n"));
+    assert!(text.contains("exclude: $\n"));
+    assert!(text.contains("group: none\n"));
+}
+
+#[test]
+fn state_changes_mark_app_dirty() {
+    let mut app = App::new(&Config::default());
+    assert!(app.dirty);
+
+    app.dirty = false;
+    app.generate_password();
+    assert!(app.dirty);
+
+    app.dirty = false;
+    app.update_value(1);
+    assert!(app.dirty);
+
+    app.dirty = false;
+    app.flash_until = None;
+    app.tick();
+    assert!(!app.dirty);
+    app.status = "Copied to clipboard.".to_string();
+    app.status_until = Some(Instant::now());
+    app.tick();
+    assert!(app.dirty);
+    assert!(app.status.is_empty());
+
+    app.dirty = false;
+    assert!(app.copy_text("x", "x"));
+    assert!(app.dirty);
+
+    // a pending clear redraws when its whole-second countdown ticks, not every poll
+    app.clipboard_clear_at = Some(Instant::now() + Duration::from_millis(30_500));
+    app.tick();
+    app.dirty = false;
+    app.tick();
+    app.tick();
+    assert!(!app.dirty);
+    app.clipboard_clear_at = Some(Instant::now() + Duration::from_millis(29_500));
+    app.tick();
+    assert!(app.dirty);
+
+    app.dirty = false;
+    app.toggle_clipboard_clear_pause();
+    assert!(app.dirty);
+    app.dirty = false;
+    app.tick();
+    assert!(app.dirty);
+    app.dirty = false;
+    app.tick();
+    assert!(!app.dirty);
+}
+
+#[test]
+fn private_copies_request_clipboard_exclusion_formats() {
+    let app = App::new(&Config::default());
+    let formats: Vec<&str> = clipboard_exclusions(app.clipboard_private)
+        .iter()
+        .map(|exclusion| exclusion.format_name())
+        .collect();
+    assert_eq!(
+        formats,
+        [
+            "ExcludeClipboardContentFromMonitorProcessing",
+            "CanIncludeInClipboardHistory",
+            "CanUploadToCloudClipboard",
+        ]
+    );
+
+    let config = Config::from_args(["--clipboard-history".to_string()]).unwrap();
+    assert!(clipboard_exclusions(App::new(&config).clipboard_private).is_empty());
+}
+
+#[test]
+fn text_input_edits_at_cursor() {
+    let mut input = TextInput::new("ac");
+    assert_eq!(input.cursor, 2);
+    input.move_left();
+    input.insert('b');
+    assert_eq!((input.text.as_str(), input.cursor), ("abc", 2));
+    input.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    input.insert_str("€!");
+    assert_eq!((input.text.as_str(), input.cursor), ("€!abc", 2));
+    input.handle_key(KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(input.text, "€!bc");
+    input.backspace();
+    input.backspace();
+    assert_eq!((input.text.as_str(), input.cursor), ("bc", 0));
+}
+
+#[test]
+fn ctrl_c_quits_from_open_prompts() {
+    let ctrl_c = (KeyCode::Char('c'), KeyModifiers::CONTROL);
+    let mut app = App::new(&Config::default());
+    app.open_symbol_edit();
+    let before = app.symbol_edit.clone();
+    assert_eq!(
+        handle_modal_key(&mut app, ctrl_c.0, ctrl_c.1),
+        KeyFlow::Quit
+    );
+    assert_eq!(app.symbol_edit, before);
+
+    app.symbol_edit = None;
+    app.begin_field_edit();
+    assert_eq!(
+        handle_modal_key(&mut app, ctrl_c.0, ctrl_c.1),
+        KeyFlow::Quit
+    );
+    app.field_edit = None;
+    app.history_filter = Some(String::new());
+    assert_eq!(
+        handle_modal_key(&mut app, ctrl_c.0, ctrl_c.1),
+        KeyFlow::Quit
+    );
+    assert_eq!(app.history_filter.as_deref(), Some(""));
+
+    // other shortcuts in a prompt are dropped rather than typed
+    app.history_filter = None;
+    app.label_edit = Some(TextInput::default());
+    let flow = handle_modal_key(&mut app, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert_eq!(flow, KeyFlow::Handled);
+    let flow = handle_modal_key(&mut app, KeyCode::Char('x'), KeyModifiers::ALT);
+    assert_eq!(flow, KeyFlow::Handled);
+    assert_eq!(app.label_edit, Some(TextInput::default()));
+    app.label_edit = None;
+    let flow = handle_modal_key(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+    assert_eq!(flow, KeyFlow::Unhandled);
+}
+
+#[test]
+fn text_input_stays_within_bounds() {
+    let mut input = TextInput::default();
+    input.backspace();
+    input.handle_key(KeyCode::Delete, KeyModifiers::NONE);
+    input.move_left();
+    input.move_right();
+    assert_eq!(input, TextInput::default());
+
+    let mut input = TextInput::new("ab");
+    input.handle_key(KeyCode::End, KeyModifiers::NONE);
+    input.handle_key(KeyCode::Delete, KeyModifiers::NONE);
+    input.move_right();
+    assert_eq!((input.text.as_str(), input.cursor), ("ab", 2));
+    let spans = input.spans(Style::default());
+    assert_eq!(spans[1].content, " ");
+    assert!(spans[1].style.add_modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn toggling_a_class_off_and_on_restores_its_count() {
+    let mut app = App::new(&Config::default());
+    app.symbols = 5;
+    app.toggle_class(CharClass::Symbols);
+    assert_eq!(app.symbols, 0);
+    app.toggle_class(CharClass::Symbols);
+    assert_eq!(app.symbols, 5);
+
+    app.numbers = 0;
+    app.toggle_class(CharClass::Numbers);
+    assert_eq!(app.numbers, 1);
+}
+
+#[test]
+fn clipboard_commands_match_platform() {
+    assert_eq!(copy_command(true), "pbcopy");
+    assert_eq!(copy_command(false), "wl-copy");
+    assert_eq!(paste_command(true), ("pbpaste", &[][..]));
+    assert_eq!(paste_command(false).0, "wl-paste");
+}
+
+#[test]
+fn output_scroll_follows_wrapped_lines() {
+    assert_eq!(wrapped_lines(0, 40), 1);
+    assert_eq!(wrapped_lines(40, 40), 1);
+    assert_eq!(wrapped_lines(41, 40), 2);
+    assert_eq!(max_output_scroll(14, 40), 0);
+    assert_eq!(max_output_scroll(128, 40), 3);
+
+    let mut app = App::new(&Config::default());
+    app.password = "a".repeat(128);
+    app.scroll_output(10, 40);
+    assert_eq!(app.output_scroll, 3);
+    app.scroll_output(-1, 40);
+    assert_eq!(app.output_scroll, 2);
+    app.scroll_output(-5, 40);
+    assert_eq!(app.output_scroll, 0);
+}
+
+#[test]
+fn generate_many_matches_single_calls() {
+    let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+    let spec = PasswordSpec {
+        letters: Some(4),
+        uppercase: Some(3),
+        symbols: Some(2),
+        numbers: Some(5),
+        ..PasswordSpec::default()
+    };
+    let many = generate_many(&spec, &pools, 50, &mut StdRng::seed_from_u64(31), |_| {});
+
+    let mut rng = StdRng::seed_from_u64(31);
+    let single: Vec<String> = (0..50)
+        .map(|_| generate_from_pools(4, 3, 2, 5, &pools, &mut rng))
+        .collect();
+    assert_eq!(many, single);
+}
+
+#[test]
+fn bulk_fill_draws_every_pool_character_evenly() {
+    // a 3-character pool exercises the rejection step that keeps the draw unbiased
+    let pool = ['a', 'b', 'c'];
+    let many = bulk::generate_many(
+        [3000, 0, 0, 0],
+        [&pool, &[], &[], &[]],
+        1,
+        &mut StdRng::seed_from_u64(8),
+        |_| {},
+    );
+    for ch in pool {
+        let seen = many[0].chars().filter(|&c| c == ch).count();
+        assert!((850..1150).contains(&seen), "{ch}: {seen}");
+    }
+}
+
+#[test]
+fn overlap_ratio_counts_shared_distinct_characters() {
+    assert_eq!(overlap_ratio("", "abc"), 0.0);
+    assert_eq!(overlap_ratio("abcd", "xyz"), 0.0);
+    assert_eq!(overlap_ratio("aabb", "b"), 0.5);
+    assert_eq!(overlap_ratio("abcd", "dcba"), 1.0);
+}
+
+#[test]
+fn avoid_rejects_similar_candidates() {
+    let old = "Tr0ub4dor&3xyz";
+    let config = Config {
+        avoid: Some(old.to_string()),
+        ..Config::default()
+    };
+    let app = App::new(&config);
+    assert!(!app.too_similar);
+    assert!(overlap_ratio(&app.password, old) <= MAX_AVOID_OVERLAP);
+
+    let config = Config {
+        letters: 6,
+        uppercase: 0,
+        symbols: 0,
+        numbers: 0,
+        avoid: Some("abcdefghijklmnopqrstuvwxyz".to_string()),
+        ..Config::default()
+    };
+    let app = App::new(&config);
+    assert!(app.too_similar);
+    assert!(app.status.contains("previous password"));
+}
+
+#[test]
+fn batch_rows_follow_the_spec() {
+    let mut app = App::new(&Config::default());
+    app.toggle_batch();
+    for row in app.batch.as_ref().unwrap() {
+        assert_eq!(row.password.len(), 14);
+        assert_eq!(
+            row.strength,
+            check_password_strength(&row.password, STRENGTH_MIN_LENGTH)
+        );
+        assert_eq!(row.bits, estimate_entropy(&app));
+    }
+}
+
+#[test]
+fn batch_rows_pass_the_same_checks_as_single_passwords() {
+    let mut app = App::new(&Config::default());
+    app.pattern = Some(Regex::new("^[a-z]").unwrap());
+    app.denylist = vec!["a".to_string()];
+    app.min_strength = Strength::Strong;
+    app.toggle_batch();
+    for row in app.batch.as_ref().unwrap() {
+        assert!(row.password.starts_with(|ch: char| ch.is_ascii_lowercase()));
+        assert!(!row.password.contains('a'));
+        assert_eq!(row.strength, Strength::Strong);
+    }
+}
+
+#[test]
+fn batch_bits_follow_each_passphrase_row() {
+    let mut app = App::new(&Config::default());
+    app.mode = Mode::Passphrase;
+    app.passphrase_style.capitalize_one = true;
+    app.toggle_batch();
+    for row in app.batch.as_ref().unwrap() {
+        assert_eq!(row.bits, entropy_for(&app, &row.password));
+    }
+    let bits: HashSet<u64> = app
+        .batch
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|row| row.bits.to_bits())
+        .collect();
+    assert!(bits.len() > 1);
+}
+
+#[test]
+fn typed_digits_reach_rows_past_nine() {
+    let mut app = App::new(&Config::default());
+    app.batch_size = 20;
+    app.toggle_batch();
+    take_copies();
+    app.type_batch_digit(1);
+    assert_eq!((app.batch_selected, app.copied_total), (0, 0));
+    app.type_batch_digit(4);
+    assert_eq!(app.batch_selected, 13);
+    assert_eq!(
+        take_copies(),
+        [app.batch.as_ref().unwrap()[13].password.as_str()]
+    );
+    app.type_batch_digit(3);
+    assert_eq!(app.batch_selected, 2);
+    app.type_batch_digit(0);
+    assert_eq!(app.copied_total, 2);
+
+    app.batch_size = 9;
+    app.toggle_batch();
+    app.toggle_batch();
+    app.type_batch_digit(1);
+    assert_eq!(app.copied_total, 3);
+}
+
+#[test]
+fn batch_selection_stays_in_bounds() {
+    assert_eq!(clamp_selection(5, 3), 2);
+    assert_eq!(clamp_selection(0, 0), 0);
+
+    let mut app = App::new(&Config::default());
+    app.toggle_batch();
+    app.move_batch_selection(100);
+    assert_eq!(app.batch_selected, BATCH_SIZE - 1);
+    let kept = app.batch.as_ref().unwrap()[0].clone();
+    app.resize_batch(-6);
+    assert_eq!(app.batch.as_ref().unwrap().len(), BATCH_SIZE - 6);
+    assert_eq!(app.batch_selected, BATCH_SIZE - 7);
+    assert_eq!(app.batch.as_ref().unwrap()[0], kept);
+    app.resize_batch(100);
+    assert_eq!(app.batch.as_ref().unwrap().len(), MAX_BATCH_SIZE);
+    app.move_batch_selection(-100);
+    assert_eq!(app.batch_selected, 0);
+}
+
+#[test]
+fn footer_shows_compiled_version() {
+    assert!(
+        footer_text(RngSource::Thread, "").contains(&format!("v{}", env!("CARGO_PKG_VERSION")))
+    );
+}
+
+#[test]
+fn match_regex_filters_candidates() {
+    let config = Config::from_args(["--match", "^[a-z].*[0-9]$"].map(String::from)).unwrap();
+    let mut app = App::new(&config);
+    for _ in 0..20 {
+        app.generate_password();
+        assert!(!app.unmatched);
+        assert!(
+            app.pattern
+                .as_ref()
+                .unwrap()
+                .is_match(&app.password)
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn match_regex_supports_lookahead() {
+    let config =
+        Config::from_args(["--match", r"^(?=.*[A-Z])(?=.*\d).{12,}$"].map(String::from)).unwrap();
+    let mut app = App::new(&config);
+    for _ in 0..20 {
+        app.generate_password();
+        assert!(!app.unmatched);
+        assert!(app.password.chars().any(|ch| ch.is_ascii_uppercase()));
+        assert!(app.password.chars().any(|ch| ch.is_ascii_digit()));
+    }
+    app.numbers = 0;
+    app.generate_password();
+    assert!(app.unmatched);
+}
+
+#[test]
+fn impossible_match_regex_reports_failure() {
+    let config = Config::from_args(["--match", "^x{100}$"].map(String::from)).unwrap();
+    let app = App::new(&config);
+    assert!(app.unmatched);
+    assert!(app.status.contains("--match"));
+
+    assert!(Config::from_args(["--match", "(unclosed"].map(String::from)).is_err());
+}
+
+#[test]
+fn profiles_round_trip_through_text() {
+    let mut profiles = HashMap::new();
+    profiles.insert(
+        "wifi".to_string(),
+        PasswordSpec {
+            letters: Some(10),
+            uppercase: Some(4),
+            symbols: Some(2),
+            numbers: Some(4),
+            ..PasswordSpec::default()
+        },
+    );
+    profiles.insert(
+        "pin".to_string(),
+        PasswordSpec {
+            numbers: Some(6),
+            ..PasswordSpec::default()
+        },
+    );
+    let text = format_profiles(&profiles);
+    assert!(text.starts_with("[\"pin\"]\nnumbers = 6\n"));
+    assert_eq!(parse_profiles(&text), profiles);
+    assert_eq!(
+        parse_profiles("# mine\n[work]\nletters = 9\nbogus = 1\nsymbols = x\n"),
+        HashMap::from([(
+            "work".to_string(),
+            PasswordSpec {
+                letters: Some(9),
+                ..PasswordSpec::default()
+            }
+        )])
+    );
+}
+
+#[test]
+fn loading_a_profile_applies_its_counts() {
+    let mut config = Config::default();
+    config.profiles.insert(
+        "work".to_string(),
+        PasswordSpec {
+            letters: Some(3),
+            numbers: Some(9),
+            ..PasswordSpec::default()
+        },
+    );
+    let mut app = App::new(&config);
+    app.open_profiles();
+    assert_eq!(app.profile_selected, Some(0));
+    app.load_selected_profile();
+    assert_eq!(
+        (app.letters, app.uppercase, app.symbols, app.numbers),
+        (3, 2, 2, 9)
+    );
+    assert_eq!(app.password.chars().count(), 16);
+    assert_eq!(app.profile_selected, None);
+
+    let parsed = Config::from_args_over(
+        config,
+        ["--profile", "work", "--letters", "5"].map(String::from),
+    )
+    .unwrap();
+    assert_eq!((parsed.letters, parsed.numbers), (5, 9));
+    assert!(Config::from_args(["--profile", "home"].map(String::from)).is_err());
+}
+
+#[test]
+fn profiles_carry_toggles_and_quoted_names() {
+    let mut app = App::new(&Config::default());
+    app.no_ambiguous = true;
+    app.leading_letter = true;
+    let profiles = HashMap::from([("a \"b\" [c]".to_string(), app.profile())]);
+    let text = format_profiles(&profiles);
+    assert!(text.starts_with("[\"a \\\"b\\\" [c]\"]\n"));
+    assert!(text.contains("no_ambiguous = true\nno_sequences = false\n"));
+    assert_eq!(parse_profiles(&text), profiles);
+
+    let mut app = App::new(&Config {
+        profiles: parse_profiles(&text),
+        ..Config::default()
+    });
+    assert!(!app.no_ambiguous);
+    app.open_profiles();
+    app.load_selected_profile();
+    assert!(app.no_ambiguous && app.leading_letter && !app.no_sequences);
+
+    let config = Config::from_args_over(
+        Config {
+            profiles: parse_profiles("[x]\nno_leading_zero = true\nallow_space = nope\n"),
+            ..Config::default()
+        },
+        ["--profile", "x"].map(String::from),
+    )
+    .unwrap();
+    assert!(config.no_leading_zero);
+    assert!(!config.allow_space);
+}
+
+#[test]
+fn loaded_profile_counts_are_clamped() {
+    let mut app = App::new(&Config {
+        profiles: parse_profiles("[huge]\nletters = 5000\nnumbers = -3\n"),
+        ..Config::default()
+    });
+    app.open_profiles();
+    app.load_selected_profile();
+    assert_eq!(app.letters, DEFAULT_MAX_VALUE);
+    assert_eq!(app.numbers, MIN_VALUE);
+}
+
+#[test]
+fn symbol_set_normalizes_and_rejects_invisible_marks() {
+    assert_eq!(sanitize_symbol_set("!@#$%^&*").unwrap(), chars("!@#$%^&*"));
+    assert_eq!(sanitize_symbol_set("！＃").unwrap(), chars("!#"));
+    assert_eq!(
+        sanitize_symbol_set("!\u{301}"),
+        Err(SymbolSetError::Combining('\u{301}'))
+    );
+    assert_eq!(
+        sanitize_symbol_set("!\u{200B}#"),
+        Err(SymbolSetError::ZeroWidth('\u{200B}'))
+    );
+    assert_eq!(
+        SymbolSetError::Combining('\u{301}').to_string(),
+        "U+0301 is a combining mark, not a symbol."
+    );
+}
+
+#[test]
+fn session_counters_track_each_action() {
+    let mut app = App::new(&Config::default());
+    assert_eq!((app.generated_total, app.copied_total), (1, 0));
+    app.generate_password();
+    app.generate_password();
+    assert_eq!(app.generated_total, 3);
+    take_copies();
+    assert!(app.copy_text("x", "x"));
+    assert_eq!(app.copied_total, 1);
+    assert_eq!(take_copies(), ["x"]);
+    assert_eq!(stats_lines(&app)[0], "Generated  3");
+}
+
+#[test]
+fn type_countdown_ticks_down_then_fires() {
+    let start = Instant::now();
+    let mut countdown = TypeCountdown::new(start);
+    assert_eq!(countdown.advance(start), CountdownStep::Waiting);
+    assert_eq!(
+        countdown.advance(start + Duration::from_millis(999)),
+        CountdownStep::Waiting
+    );
+    assert_eq!(
+        countdown.advance(start + Duration::from_secs(1)),
+        CountdownStep::Tick(2)
+    );
+    assert_eq!(
+        countdown.advance(start + Duration::from_millis(1500)),
+        CountdownStep::Waiting
+    );
+    assert_eq!(
+        countdown.advance(start + Duration::from_secs(2)),
+        CountdownStep::Tick(1)
+    );
+    assert_eq!(
+        countdown.advance(start + Duration::from_secs(3)),
+        CountdownStep::Fire
+    );
+}
+
+#[test]
+fn type_countdown_clears_after_firing() {
+    let mut app = App::new(&Config::default());
+    let start = Instant::now();
+    app.type_countdown = Some(TypeCountdown::new(start));
+    app.advance_type_countdown(start + Duration::from_secs(1));
+    assert_eq!(app.status, countdown_message(2));
+    app.advance_type_countdown(start + Duration::from_secs(2));
+    assert_eq!(app.status, countdown_message(1));
+    // with the feature on this would send real keystrokes
+    if cfg!(not(feature = "autotype")) {
+        app.advance_type_countdown(start + Duration::from_secs(3));
+        assert!(app.type_countdown.is_none());
+        assert!(app.status.starts_with("Could not type"));
+    }
+}
+
+#[test]
+fn motion_keys_with_stray_modifiers_still_adjust() {
+    let keymap = default_keymap();
+    let mut app = App::new(&Config::default());
+    let letters = app.letters;
+    app.handle_unhandled_key(KeyCode::Char('l'), KeyModifiers::ALT, &keymap, 1);
+    assert_eq!(app.letters, letters + 1);
+    assert!(app.status.contains("Alt+l"));
+    app.handle_unhandled_key(KeyCode::Char('H'), KeyModifiers::SHIFT, &keymap, 1);
+    assert_eq!(app.letters, letters);
+    assert_eq!(app.key_log[0], "Shift+H -> Dec");
+
+    app.handle_unhandled_key(KeyCode::Char('x'), KeyModifiers::CONTROL, &keymap, 1);
+    assert_eq!(app.letters, letters);
+    assert_eq!(
+        app.status,
+        "No binding for Ctrl+x. Press F12 for the key log."
+    );
+    assert_eq!(app.key_log.len(), 3);
+
+    assert_eq!(
+        relaxed_motion(&keymap, KeyCode::Char('q'), KeyModifiers::ALT),
+        None
+    );
+    assert_eq!(
+        relaxed_motion(&keymap, KeyCode::Char('h'), KeyModifiers::NONE),
+        None
+    );
+    for (ch, action) in [
+        ('H', Action::Dec),
+        ('J', Action::FocusDown),
+        ('K', Action::FocusUp),
+        ('L', Action::Inc),
+    ] {
+        assert_eq!(
+            relaxed_motion(&keymap, KeyCode::Char(ch), KeyModifiers::SHIFT),
+            Some(action)
+        );
+    }
+}
+
+#[test]
+fn clear_password_empties_output() {
+    let mut app = App::new(&Config::default());
+    app.generate_password();
+    app.history_filter = Some("a".to_string());
+    app.clear_password();
+    assert!(app.password.is_empty());
+    assert!(app.previous_password.is_empty());
+    assert_eq!(app.strength, Strength::DoNotUse);
+    assert_eq!(app.history_filter, None);
+    assert_eq!(app.status, "Cleared.");
+    assert_eq!(app.copy_payload(), "");
+    app.copy_password();
+    assert_eq!(app.status, "Nothing to copy.");
+}
+
+#[test]
+fn clear_password_leaves_nothing_to_render() {
+    let mut app = App::new(&Config::default());
+    app.generate_password();
+    app.pin_compare();
+    app.generate_password();
+    app.toggle_batch();
+    let mut shown: Vec<String> = app.history.clone();
+    shown.push(app.password.clone());
+    shown.extend(app.compare_slot.clone());
+    shown.extend(app.batch.iter().flatten().map(|row| row.password.clone()));
+    app.clear_password();
+    assert!(app.compare_slot.is_none() && app.batch.is_none());
+    assert!(app.history.is_empty());
+
+    let backend = ratatui::backend::TestBackend::new(100, 60);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal.draw(|frame| ui(frame, &app)).unwrap();
+    let text: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+    for password in shown {
+        assert!(!text.contains(&password), "{password} still shown");
+    }
+}
+
+#[test]
+fn layout_parses_class_letters() {
+    let layout = parse_layout("u?sn").unwrap();
+    assert_eq!(
+        layout,
+        vec![
+            Some(CharClass::Uppercase),
+            None,
+            Some(CharClass::Symbols),
+            Some(CharClass::Numbers)
+        ]
+    );
+    assert_eq!(format_layout(&layout), "u?sn");
+    assert_eq!(parse_layout("lx"), None);
+    assert!(Config::from_args(["--layout", "L"].map(String::from)).is_err());
+}
+
+#[test]
+fn layout_places_classes_at_their_positions() {
+    let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+    let layout = parse_layout("u??????????snn").unwrap();
+    for seed in 0..50 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut chars: Vec<char> = generate_from_pools(6, 2, 2, 4, &pools, &mut rng)
+            .chars()
+            .collect();
+        let mut before = chars.clone();
+        apply_layout(&mut chars, &layout, &pools, &mut rng);
+        assert_eq!(chars.len(), 14);
+        for (ch, class) in chars.iter().zip(&layout) {
+            if let Some(class) = class {
+                assert_eq!(CharClass::of(*ch), *class);
+            }
+        }
+        // the counts already cover the layout, so nothing new is drawn
+        let mut after = chars.clone();
+        before.sort_unstable();
+        after.sort_unstable();
+        assert_eq!(after, before);
+    }
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut chars: Vec<char> = "abcd".chars().collect();
+    apply_layout(&mut chars, &parse_layout("?ss").unwrap(), &pools, &mut rng);
+    assert_eq!(chars.len(), 4);
+    assert_eq!(CharClass::of(chars[1]), CharClass::Symbols);
+    assert_eq!(CharClass::of(chars[2]), CharClass::Symbols);
+}
+
+#[test]
+fn repairs_keep_the_layout() {
+    let mut app = App::new(&Config::default());
+    (app.letters, app.uppercase, app.symbols, app.numbers) = (6, 2, 4, 4);
+    app.layout = parse_layout("nss???????????uu").unwrap();
+    app.no_sequences = true;
+    app.no_leading_zero = true;
+    app.max_symbol_run = Some(2);
+    app.leading_letter = true;
+    let pools = app.pools();
+    let mut rng = StdRng::seed_from_u64(4);
+    for _ in 0..100 {
+        let chars: Vec<char> = app.generate_candidate(&pools, &mut rng).chars().collect();
+        for (ch, class) in chars.iter().zip(&app.layout) {
+            if let Some(class) = class {
+                assert_eq!(CharClass::of(*ch), *class, "{chars:?}");
+            }
+        }
+        assert_eq!(find_sequence(&chars), None, "{chars:?}");
+        assert_ne!(chars[0], '0');
+        assert_eq!(find_class_run(&chars, CharClass::Symbols, 2), None);
+    }
+
+    // a pinned first digit cannot be swapped for a letter
+    let mut chars: Vec<char> = "1ab".chars().collect();
+    let layout = parse_layout("n").unwrap();
+    assert!(!ensure_leading_letter(&mut chars, &layout));
+    assert_eq!(chars[0], '1');
+}
+
+#[test]
+fn embedded_wordlist_is_valid() {
+    assert_eq!(validate_wordlist(&wordlist()), Ok(()));
+}
+
+#[test]
+fn wordlist_validation_rejects_duplicates_and_blanks() {
+    let mut words: Vec<String> = (0..MIN_WORDLIST_WORDS)
+        .map(|i| format!("word{i}"))
+        .collect();
+    let refs: Vec<&str> = words.iter().map(String::as_str).collect();
+    assert_eq!(validate_wordlist(&refs), Ok(()));
+
+    words[7] = "word3".to_string();
+    let refs: Vec<&str> = words.iter().map(String::as_str).collect();
+    assert_eq!(
+        validate_wordlist(&refs),
+        Err(WordlistError::Duplicate("word3".to_string()))
+    );
+
+    words[7] = String::new();
+    let refs: Vec<&str> = words.iter().map(String::as_str).collect();
+    assert_eq!(validate_wordlist(&refs), Err(WordlistError::EmptyEntry(8)));
+}
+
+#[test]
+fn wordlist_validation_enforces_minimum_size() {
+    let words: Vec<String> = (1..MIN_WORDLIST_WORDS)
+        .map(|i| format!("word{i}"))
+        .collect();
+    let refs: Vec<&str> = words.iter().map(String::as_str).collect();
+    assert_eq!(
+        validate_wordlist(&refs),
+        Err(WordlistError::TooShort(MIN_WORDLIST_WORDS - 1))
+    );
+}
+
+#[test]
+fn auto_copy_fires_once_per_explicit_generation() {
+    let config = Config::from_args(["--auto-copy".to_string()]).unwrap();
+    let mut app = App::new(&config);
+    app.status.clear();
+    app.step_value(1, 1);
+    app.tick();
+    assert!(app.status.is_empty());
+
+    app.regenerate();
+    app.regenerate();
+    assert!(app.auto_copy_pending);
+    app.tick();
+    assert!(!app.auto_copy_pending);
+    assert!(!app.status.is_empty());
+
+    app.status.clear();
+    app.tick();
+    assert!(app.status.is_empty());
+
+    let mut app = App::new(&Config::default());
+    app.regenerate();
+    assert!(!app.auto_copy_pending);
+}
+
+#[test]
+fn checksum_is_stable_and_sensitive() {
+    assert_eq!(checksum("hunter2"), checksum("hunter2"));
+    assert_eq!(checksum("hunter2").len(), 2);
+    assert_ne!(checksum("hunter2"), checksum("hunter3"));
+    assert_ne!(checksum("ab"), checksum("ba"));
+    // low byte of the standard CRC-32 check value 0xCBF43926
+    assert_eq!(checksum("123456789"), "26");
+}
+
+#[test]
+fn os_rng_generates_valid_passwords() {
+    let config = Config::from_args(["--rng", "os"].map(String::from)).unwrap();
+    assert_eq!(config.rng_source, RngSource::Os);
+    let mut app = App::new(&config);
+    assert_eq!(app.rng_source, RngSource::Os);
+    assert!(footer_text(app.rng_source, &app.key_hints).contains("OS RNG"));
+    for _ in 0..20 {
+        app.generate_password();
+        assert_eq!(app.password.chars().count(), 14);
+        let pools = app.pools();
+        assert!(
+            app.password
+                .chars()
+                .all(|ch| pools.class_of(ch).contains(&ch))
+        );
+    }
+    assert_eq!(app.batch_rows(3).len(), 3);
+
+    assert_eq!(Config::default().rng_source, RngSource::Thread);
+    assert!(Config::from_args(["--rng", "hw"].map(String::from)).is_err());
+}
+
+#[test]
+fn passphrase_styles_transform_the_phrase() {
+    let phrase = "alpha-bravo-charlie";
+    let symbols = ['!'];
+    let mut rng = StdRng::seed_from_u64(3);
+    let style = |update: fn(&mut PassphraseStyle)| {
+        let mut style = PassphraseStyle::default();
+        update(&mut style);
+        style
+    };
+
+    let plain = PassphraseStyle::default();
+    assert_eq!(
+        apply_passphrase_style(phrase, &plain, &symbols, &mut rng),
+        phrase
+    );
+
+    let title = style(|s| s.title_case = true);
+    assert_eq!(
+        apply_passphrase_style(phrase, &title, &symbols, &mut rng),
+        "Alpha-Bravo-Charlie"
+    );
+
+    let one = style(|s| s.capitalize_one = true);
+    let styled = apply_passphrase_style(phrase, &one, &symbols, &mut rng);
+    assert_eq!(styled.chars().filter(char::is_ascii_uppercase).count(), 1);
+    assert_eq!(styled.to_ascii_lowercase(), phrase);
+
+    let number = style(|s| s.append_number = true);
+    let styled = apply_passphrase_style(phrase, &number, &symbols, &mut rng);
+    let (head, digits) = styled.split_at(phrase.len());
+    assert_eq!(head, phrase);
+    assert!(digits.len() == 2 && digits.chars().all(|ch| ch.is_ascii_digit()));
+
+    let symbol = style(|s| s.insert_symbol = true);
+    let styled = apply_passphrase_style(phrase, &symbol, &symbols, &mut rng);
+    assert_eq!(styled.matches('!').count(), 1);
+    assert_eq!(styled.matches(PASSPHRASE_SEPARATOR).count(), 1);
+    assert_eq!(count_words(&styled), 3);
+}
+
+#[test]
+fn passphrase_styles_add_to_the_bit_estimate() {
+    let phrase = "alpha-bravo-charlie";
+    assert_eq!(
+        passphrase_style_bits(&PassphraseStyle::default(), phrase, 32),
+        0.0
+    );
+    let number = PassphraseStyle {
+        append_number: true,
+        ..PassphraseStyle::default()
+    };
+    assert!((passphrase_style_bits(&number, phrase, 32) - 100f64.log2()).abs() < 1e-9);
+    let symbol = PassphraseStyle {
+        insert_symbol: true,
+        ..PassphraseStyle::default()
+    };
+    assert!((passphrase_style_bits(&symbol, phrase, 32) - 6.0).abs() < 1e-9);
+    let one = PassphraseStyle {
+        capitalize_one: true,
+        ..PassphraseStyle::default()
+    };
+    assert!((passphrase_style_bits(&one, phrase, 32) - 17f64.log2()).abs() < 1e-9);
+
+    let plain = App::new(&Config::from_args(["--passphrase".to_string()]).unwrap());
+    let config =
+        Config::from_args(["--passphrase", "--title-case", "--append-number"].map(String::from))
+            .unwrap();
+    let styled = App::new(&config);
+    assert!(estimate_entropy(&styled) > estimate_entropy(&plain));
+    assert!(
+        styled
+            .password
+            .starts_with(|ch: char| ch.is_ascii_uppercase())
+    );
+    assert_eq!(
+        count_words(&styled.password),
+        DEFAULT_PASSPHRASE_WORDS as usize
+    );
+}
+
+#[test]
+fn distinct_only_never_repeats_a_character() {
+    let config =
+        Config::from_args(["--distinct", "--letters", "20", "--numbers", "10"].map(String::from))
+            .unwrap();
+    let mut app = App::new(&config);
+    for _ in 0..20 {
+        app.generate_password();
+        let mut chars: Vec<char> = app.password.chars().collect();
+        assert_eq!(chars.len(), 34);
+        chars.sort_unstable();
+        chars.dedup();
+        assert_eq!(chars.len(), 34);
+    }
+}
+
+#[test]
+fn distinct_only_rejects_counts_beyond_the_pool() {
+    let config = Config::from_args(["--distinct", "--numbers", "11"].map(String::from)).unwrap();
+    let mut app = App::new(&config);
+    assert!(app.password.is_empty());
+    assert_eq!(app.distinct_shortfall(&app.pools()), vec!["numbers"]);
+    assert_eq!(
+        app.status,
+        "Not enough distinct numbers for the requested count."
+    );
+
+    app.numbers = 10;
+    app.generate_password();
+    assert_eq!(
+        app.password.chars().filter(char::is_ascii_digit).count(),
+        10
+    );
+}
+
+#[test]
+fn field_help_describes_each_focus() {
+    let expected = [
+        "Number of lowercase letters a\u{2013}z",
+        "Number of uppercase letters A\u{2013}Z",
+        "Number of symbols drawn from the symbol set",
+        "Number of digits 0\u{2013}9",
+        "Generate a new password with these settings",
+        "Copy the password to the clipboard",
+        "Quit GenPass",
+    ];
+    for (focus, help) in expected.iter().enumerate() {
+        assert_eq!(field_help(focus), *help);
+    }
+    assert_eq!(field_help(FOCUS_QUIT + 1), "");
+}
+
+#[test]
+fn marker_x_scales_across_the_gauge() {
+    let rect = Rect::new(5, 2, 41, 1);
+    assert_eq!(marker_x(rect, 0.0), 5);
+    assert_eq!(marker_x(rect, 0.5), 25);
+    assert_eq!(marker_x(rect, 1.0), 45);
+    assert_eq!(marker_x(rect, 1.7), 45);
+    assert_eq!(marker_x(Rect::new(3, 0, 1, 1), 0.5), 3);
+    assert_eq!(target_bits(Strength::DoNotUse), None);
+    assert_eq!(target_bits(Strength::Strong), Some(60.0));
+}
+
+#[test]
+fn confusable_groups_are_removed_from_every_pool() {
+    let pool: Vec<char> = "0Oo1lIS5Zz".chars().collect();
+    assert_eq!(
+        filter_confusables(&pool, &["0Oo", "5S"]),
+        vec!['1', 'l', 'I', 'Z', 'z']
+    );
+    assert_eq!(filter_confusables(&pool, &[]), pool);
+
+    for (index, group) in CONFUSABLE_GROUPS.iter().enumerate() {
+        let mut app = App::new(&Config::default());
+        app.symbol_set = chars_of(SYMBOLS);
+        app.symbol_set.push('|');
+        app.toggle_confusable(index);
+        let pools = app.pools();
+        for pool in [
+            &pools.lowercase,
+            &pools.uppercase,
+            &pools.symbols,
+            &pools.numbers,
+        ] {
+            assert!(
+                pool.iter().all(|&ch| !group.contains(ch)),
+                "{group} in {pool:?}"
+            );
+        }
+        app.toggle_confusable(index);
+        assert!(app.pools().numbers.len() == 10);
+    }
+
+    let config = Config::from_args(["--confusable", "5S,8B"].map(String::from)).unwrap();
+    assert_eq!(active_confusables(&config.confusables), vec!["5S", "8B"]);
+    assert!(Config::from_args(["--confusable", "xyz"].map(String::from)).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn wait_with_timeout_kills_a_hung_command() {
+    let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+    let started = Instant::now();
+    let status = wait_with_timeout(&mut child, Duration::from_millis(100)).unwrap();
+    assert_eq!(status, None);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(child.try_wait().unwrap().is_some());
+
+    let mut child = Command::new("true").spawn().unwrap();
+    let status = wait_with_timeout(&mut child, Duration::from_secs(2)).unwrap();
+    assert!(status.is_some_and(|status| status.success()));
+}
+
+#[test]
+fn shell_quote_escapes_single_quotes() {
+    assert_eq!(shell_quote("abc"), "'abc'");
+    assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    assert_eq!(shell_quote("''"), "''\\'''\\'''");
+    assert_eq!(shell_quote("$x `y` \\"), "'$x `y` \\'");
+    assert_eq!(
+        export_line("DB_PASSWORD", "a'b"),
+        "export DB_PASSWORD='a'\\''b'"
+    );
+}
+
+#[test]
+fn export_names_set_the_count() {
+    let config = Config::from_args(["--export", "A", "--export", "_B2"].map(String::from)).unwrap();
+    assert_eq!(config.exports, vec!["A", "_B2"]);
+    assert_eq!(config.count, 2);
+    assert!(config.print);
+    assert!(Config::from_args(["--export", "2X"].map(String::from)).is_err());
+    assert!(Config::from_args(["--export", "A-B"].map(String::from)).is_err());
+}
+
+#[test]
+fn switching_modes_restores_each_modes_focus() {
+    let mut app = App::new(&Config::default());
+    app.focus = 2;
+    app.switch_mode(Mode::Pin);
+    assert_eq!(app.focus, 0);
+    app.focus = FOCUS_COPY;
+    app.switch_mode(Mode::Random);
+    assert_eq!(app.focus, 2);
+    app.switch_mode(Mode::Pin);
+    assert_eq!(app.focus, FOCUS_COPY);
+}
+
+#[test]
+fn stored_focus_is_clamped_to_the_mode() {
+    let mut app = App::new(&Config::default());
+    app.mode_focus[Mode::Passphrase as usize] = 3;
+    app.switch_mode(Mode::Passphrase);
+    assert_eq!(app.focus, 0);
+    app.mode_focus[Mode::Random as usize] = FOCUS_QUIT + 5;
+    app.switch_mode(Mode::Random);
+    assert_eq!(app.focus, FOCUS_QUIT);
+}
+
+#[test]
+fn bits_per_char_matches_pool_sizes() {
+    assert!((bits_per_char(26) - 4.70).abs() < 0.01);
+    assert!((bits_per_char(10) - 3.32).abs() < 0.01);
+    assert_eq!(bits_per_char(1), 0.0);
+
+    let mut app = App::new(&Config::default());
+    assert_eq!(entropy_advice(&app).unwrap(), "+4.7 bits/char for letters");
+    app.focus = 3;
+    assert_eq!(entropy_advice(&app).unwrap(), "+3.3 bits/char for numbers");
+    app.focus = FOCUS_GENERATE;
+    assert_eq!(entropy_advice(&app), None);
+}
+
+#[test]
+fn spec_command_line_lists_the_counts() {
+    let app = App::new(&Config::default());
+    assert_eq!(
+        spec_command_line(&app),
+        "genpass --letters 6 --uppercase 2 --symbols 2 --numbers 4"
+    );
+}
+
+#[test]
+fn spec_command_line_round_trips_through_the_parser() {
+    let config = Config::from_args(
+        [
+            "--letters",
+            "9",
+            "--symbols",
+            "3",
+            "--symbol-set",
+            "!'$",
+            "--exclude",
+            "xyz",
+            "--confusable",
+            "0Oo,5S",
+            "--no-sequences",
+            "--shell-safe",
+            "--min-strength",
+            "moderate",
+            "--match",
+            "^[a-z]",
+            "--max",
+            "80",
+            "--strength-min-length",
+            "16",
+        ]
+        .map(String::from),
+    )
+    .unwrap();
+    let mut app = App::new(&config);
+    app.letters = 70;
+    app.no_ambiguous = true;
+    app.distinct_only = true;
+
+    let line = spec_command_line(&app);
+    assert!(line.contains("--symbol-set '!'\\''$'"));
+    let parsed = App::new(&Config::from_args(spec_args(&app)).unwrap());
+    assert_eq!(parsed.spec(), app.spec());
+    assert_eq!(parsed.mode, app.mode);
+    assert_eq!(parsed.symbol_set, app.symbol_set);
+    assert_eq!(parsed.exclude, app.exclude);
+    assert_eq!(parsed.confusables, app.confusables);
+    assert_eq!(parsed.min_strength, app.min_strength);
+    assert_eq!(
+        parsed.pattern.as_ref().map(Regex::as_str),
+        app.pattern.as_ref().map(Regex::as_str)
+    );
+    assert!(parsed.no_ambiguous && parsed.distinct_only);
+    assert!(parsed.no_sequences && parsed.shell_safe);
+    assert_eq!(parsed.max_per_field, 80);
+    assert_eq!(parsed.strength_min_length, 16);
+    assert_eq!(spec_command_line(&parsed), line);
+}
+
+#[test]
+fn spec_command_line_round_trips_pin_and_passphrase_modes() {
+    let mut app = App::new(&Config::default());
+    app.mode = Mode::Pin;
+    app.pin_digits = 9;
+    let parsed = App::new(&Config::from_args(spec_args(&app)).unwrap());
+    assert_eq!((parsed.mode, parsed.pin_digits), (Mode::Pin, 9));
+    assert!(spec_command_line(&app).contains("--pin --pin-digits 9"));
+
+    app.mode = Mode::Passphrase;
+    app.passphrase_words = 4;
+    app.passphrase_style.title_case = true;
+    let parsed = App::new(&Config::from_args(spec_args(&app)).unwrap());
+    assert_eq!(
+        (parsed.mode, parsed.passphrase_words),
+        (Mode::Passphrase, 4)
+    );
+    assert!(parsed.passphrase_style.title_case);
+    assert_eq!(spec_command_line(&parsed), spec_command_line(&app));
+
+    app.mode = Mode::Combo;
+    app.combo_digits = 5;
+    let parsed = App::new(&Config::from_args(spec_args(&app)).unwrap());
+    assert_eq!((parsed.mode, parsed.combo_digits), (Mode::Combo, 5));
+
+    assert!(Config::from_args(["--pin-digits", "3"].map(String::from)).is_err());
+    assert!(Config::from_args(["--words", "13"].map(String::from)).is_err());
+    assert!(Config::from_args(["--combo-digits", "x"].map(String::from)).is_err());
+}
+
+#[test]
+fn announcements_describe_changes_without_the_password() {
+    assert_eq!(
+        announce_generated(14, Strength::Strong),
+        "password generated, 14 chars, Strong"
+    );
+
+    let mut app = App::new(&Config::default());
+    let lines = app.announcements();
+    assert_eq!(lines[0], "Letters: 6");
+    assert_eq!(lines[1], announce_generated(14, app.strength));
+    assert!(lines.iter().all(|line| !line.contains(&app.password)));
+    assert!(app.announcements().is_empty());
+
+    app.focus = 2;
+    assert_eq!(app.announcements(), vec!["Symbols: 2"]);
+    app.focus = FOCUS_COPY;
+    assert_eq!(app.announcements(), vec!["Copy button"]);
+
+    app.generate_password();
+    assert_eq!(
+        app.announcements(),
+        vec![announce_generated(14, app.strength)]
+    );
+    app.status = "Copied to clipboard.".to_string();
+    assert_eq!(app.announcements(), vec!["Copied to clipboard."]);
+}
+
+#[test]
+fn allow_space_puts_spaces_in_passwords() {
+    let config = Config::from_args(
+        [
+            "--allow-space",
+            "--symbol-set",
+            "!",
+            "--letters",
+            "0",
+            "--uppercase",
+            "0",
+            "--numbers",
+            "0",
+            "--symbols",
+            "30",
+        ]
+        .map(String::from),
+    )
+    .unwrap();
+    let app = App::new(&config);
+    assert!(app.pools().symbols.contains(&' '));
+    assert!(app.password.contains(' '));
+    assert_eq!(app.copy_payload(), app.password);
+    assert!(!app.display_password().contains(' '));
+    assert_eq!(
+        app.display_password().chars().count(),
+        app.password.chars().count()
+    );
+
+    let plain = App::new(&Config::default());
+    assert!(!plain.pools().symbols.contains(&' '));
+}
+
+#[test]
+fn spaces_count_toward_strength() {
+    assert_eq!(
+        check_password_strength("Abcdefgh12", STRENGTH_MIN_LENGTH),
+        Strength::Moderate
+    );
+    assert_eq!(
+        check_password_strength("Abcd efgh12", STRENGTH_MIN_LENGTH),
+        Strength::Strong
+    );
+
+    let mut app = App::new(&Config::default());
+    let before = estimate_entropy(&app);
+    app.toggle_space();
+    assert!(estimate_entropy(&app) > before);
+}
+
+#[test]
+fn bulk_progress_reports_every_step_and_the_end() {
+    let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+    let spec = PasswordSpec {
+        letters: Some(4),
+        uppercase: Some(0),
+        symbols: Some(0),
+        numbers: Some(0),
+        ..PasswordSpec::default()
+    };
+    let mut reported = Vec::new();
+    let many = generate_many(&spec, &pools, 1234, &mut StdRng::seed_from_u64(5), |done| {
+        reported.push(done)
+    });
+    assert_eq!(many.len(), 1234);
+    assert_eq!(reported, vec![500, 1000, 1234]);
+
+    let mut calls = 0;
+    for done in 1..=1000 {
+        report_progress(done, 1000, &mut |_| calls += 1);
+    }
+    assert_eq!(calls, 2);
+}
+
+#[test]
+fn accelerators_act_from_any_focus() {
+    assert_eq!(accelerator(KeyCode::Char('R')), Some(Action::Generate));
+    assert_eq!(accelerator(KeyCode::Char('Y')), Some(Action::Copy));
+    assert_eq!(accelerator(KeyCode::Char('r')), None);
+
+    let mut app = App::new(&Config::default());
+    take_copies();
+    for focus in 0..=FOCUS_QUIT {
+        app.focus = focus;
+        let generated = app.generated_total;
+        app.run_accelerator(Action::Generate);
+        assert_eq!(app.generated_total, generated + 1);
+        assert_eq!(app.focus, focus);
+
+        let copied = app.copied_total;
+        app.run_accelerator(Action::Copy);
+        assert_eq!(app.copied_total, copied + 1);
+        assert_eq!(take_copies(), [app.password.as_str()]);
+        assert_eq!(app.focus, focus);
+    }
+}
+
+#[test]
+fn phonetic_spells_each_character() {
+    assert_eq!(
+        phonetic("aZ1! x~"),
+        vec![
+            "alfa",
+            "ZULU",
+            "One",
+            "Exclamation",
+            "Space",
+            "xray",
+            "Tilde"
+        ]
+    );
+    assert_eq!(phonetic("\u{e9}"), vec!["\u{e9}"]);
+    assert!(phonetic("").is_empty());
+}
+
+#[test]
+fn avoid_leading_zero_swaps_or_rerolls() {
+    let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+    let mut rng = StdRng::seed_from_u64(8);
+    let mut chars: Vec<char> = "0042".chars().collect();
+    assert!(avoid_leading_zero(&mut chars, &[], &pools, &mut rng));
+    assert_eq!(chars, vec!['4', '0', '0', '2']);
+
+    let mut chars: Vec<char> = "000".chars().collect();
+    assert!(avoid_leading_zero(&mut chars, &[], &pools, &mut rng));
+    assert_ne!(chars[0], '0');
+    assert!(chars[0].is_ascii_digit());
+
+    let zeros_only = Pools::new(&[], &['1', '2', '3', '4', '5', '6', '7', '8', '9']);
+    let mut chars = vec!['0', '0'];
+    assert!(!avoid_leading_zero(&mut chars, &[], &zeros_only, &mut rng));
+}
+
+#[test]
+fn no_leading_zero_pins_never_start_with_zero() {
+    let config = Config::from_args(["--pin", "--no-leading-zero"].map(String::from)).unwrap();
+    let mut app = App::new(&config);
+    for _ in 0..200 {
+        app.generate_password();
+        assert!(!app.password.starts_with('0'));
+    }
+
+    let config = Config::from_args(
+        [
+            "--no-leading-zero",
+            "--letters",
+            "0",
+            "--uppercase",
+            "0",
+            "--symbols",
+            "0",
+        ]
+        .map(String::from),
+    )
+    .unwrap();
+    let mut app = App::new(&config);
+    for _ in 0..200 {
+        app.generate_password();
+        assert!(!app.password.starts_with('0'));
+    }
+}
+
+#[test]
+fn class_runs_are_found_past_the_cap() {
+    let chars: Vec<char> = "ab!#c$%&d".chars().collect();
+    assert_eq!(find_class_run(&chars, CharClass::Symbols, 3), None);
+    assert_eq!(find_class_run(&chars, CharClass::Symbols, 2), Some(7));
+    assert_eq!(find_class_run(&chars, CharClass::Letters, 1), Some(1));
+}
+
+#[test]
+fn symbol_runs_never_exceed_a_cap_of_two() {
+    let specs = [
+        (6, 2, 2, 4),
+        (2, 1, 8, 1),
+        (0, 0, 10, 5),
+        (1, 1, 12, 3),
+        (4, 4, 4, 4),
+    ];
+    for (letters, uppercase, symbols, numbers) in specs {
+        let config = Config::from_args(
+            [
+                "--max-symbol-run",
+                "2",
+                "--letters",
+                &letters.to_string(),
+                "--uppercase",
+                &uppercase.to_string(),
+                "--symbols",
+                &symbols.to_string(),
+                "--numbers",
+                &numbers.to_string(),
+            ]
+            .map(String::from),
+        )
+        .unwrap();
+        let mut app = App::new(&config);
+        for _ in 0..50 {
+            app.generate_password();
+            let chars: Vec<char> = app.password.chars().collect();
+            assert_eq!(
+                find_class_run(&chars, CharClass::Symbols, 2),
+                None,
+                "{}",
+                app.password
+            );
+        }
+    }
+
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut chars: Vec<char> = "!!!!!!!a".chars().collect();
+    assert!(!break_class_runs(
+        &mut chars,
+        &[],
+        CharClass::Symbols,
+        2,
+        &mut rng
+    ));
+}
+
+#[test]
+fn generated_entries_serialize_as_escaped_json() {
+    let entry = GeneratedEntry {
+        index: 3,
+        password: "a\"b\\c\td".to_string(),
+    };
+    assert_eq!(entry.to_json(), r#"{"index":3,"password":"a\"b\\c\td"}"#);
+    assert_eq!(json_string("\u{1}"), r#""\u0001""#);
+}
+
+#[test]
+fn jsonl_rejects_other_output_targets() {
+    for extra in ["--copy", "--quiet"] {
+        assert!(Config::from_args(["--jsonl", extra].map(String::from)).is_err());
+    }
+    let config = Config::from_args(["--jsonl"].map(String::from)).unwrap();
+    assert!(config.jsonl && config.print);
+}
+
+#[test]
+fn explicit_generation_can_move_focus_to_copy() {
+    let config = Config {
+        focus_copy_after_generate: true,
+        ..Config::default()
+    };
+    let mut app = App::new(&config);
+    app.focus = 1;
+    app.step_value(1, 1);
+    assert_eq!(app.focus, 1);
+
+    app.regenerate();
+    assert_eq!(app.focus, FOCUS_COPY);
+
+    let mut app = App::new(&Config::default());
+    app.focus = 1;
+    app.regenerate();
+    assert_eq!(app.focus, 1);
+}
+
+#[test]
+fn resize_marks_dirty_and_reclamps_scroll() {
+    let mut app = App::new(&Config::default());
+    app.password = "x".repeat(200);
+    app.scroll_output(10, output_inner_width(20));
+    assert!(app.output_scroll > 0);
+    app.dirty = false;
+
+    app.resize(400);
+    assert!(app.dirty);
+    assert_eq!(app.output_scroll, 0);
+}
+
+#[test]
+fn word_combo_is_word_symbol_word_digits() {
+    let words = ["tiger", "cloud", "maple", "river"];
+    let pools = Pools::new(&['!', '#'], &[]);
+    let shape = Regex::new(r"^[A-Z][a-z]+[!#][a-z]+[0-9]{2}$").unwrap();
+    let mut rng = StdRng::seed_from_u64(11);
+    for _ in 0..50 {
+        let (combo, bits) = generate_word_combo(&words, &pools, 2, &mut rng);
+        assert!(shape.is_match(&combo).unwrap(), "{combo}");
+        let (first, rest) = combo.split_once(['!', '#']).unwrap();
+        assert!(words.contains(&first.to_lowercase().as_str()));
+        assert!(words.contains(&rest.trim_end_matches(|ch: char| ch.is_ascii_digit())));
+        // two words from 4, one symbol from 2, two digits from 10
+        assert!((bits - (2.0 * 2.0 + 1.0 + 2.0 * 10f64.log2())).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn word_combo_mode_follows_passphrase() {
+    assert_eq!(Mode::Passphrase.next(), Mode::Combo);
+    assert_eq!(Mode::Combo.next(), Mode::Random);
+    let config = Config::from_args(["--word-combo".to_string()]).unwrap();
+    let app = App::new(&config);
+    assert_eq!(app.mode, Mode::Combo);
+    assert!(app.password.ends_with(|ch: char| ch.is_ascii_digit()));
+    assert_eq!(app.field_value(), Some(DEFAULT_COMBO_DIGITS));
+}
+
+#[test]
+fn common_passwords_are_flagged() {
+    let common = common_passwords();
+    assert!(is_common_password("password1", &common));
+    assert!(is_common_password("Qwerty123", &common));
+    assert!(!is_common_password("r9)j(t1n3ifIM7", &common));
+
+    let report = format_check_report("Passw0rd", Some(&common), STRENGTH_MIN_LENGTH);
+    assert!(report.starts_with("Strength: Do not use!!!!\n"));
+    assert!(report.contains("Common password: yes"));
+    let report = format_check_report("Passw0rd", None, STRENGTH_MIN_LENGTH);
+    assert!(!report.contains("Common password"));
+}
+
+#[test]
+fn common_password_list_has_a_thousand_distinct_entries() {
+    assert_eq!(COMMON_PASSWORDS.lines().count(), 1000);
+    assert_eq!(common_passwords().len(), 1000);
+}
+
+#[test]
+fn common_check_downgrades_generated_strength() {
+    let config = Config::from_args(["--common-check".to_string()]).unwrap();
+    let app = App::new(&config);
+    assert_eq!(
+        check_password_strength("Passw0rd", STRENGTH_MIN_LENGTH),
+        Strength::Weak
+    );
+    assert_eq!(strength_for_mode(&app, "Passw0rd"), Strength::DoNotUse);
+    assert_eq!(strength_for_mode(&app, "r9)j(t1n3ifIM7"), Strength::Strong);
+}
+
+#[test]
+fn length_criterion_follows_the_threshold() {
+    for min_len in [8, 10, 14] {
+        let short = format!("Aa1!{}", "x".repeat(min_len - 5));
+        let exact = format!("Aa1!{}", "x".repeat(min_len - 4));
+        assert_eq!(check_password_strength(&short, min_len), Strength::Moderate);
+        assert_eq!(check_password_strength(&exact, min_len), Strength::Strong);
+        assert_eq!(
+            strength_criteria(&exact, min_len)[0].0,
+            format!("Length ({min_len}+)")
+        );
+    }
+
+    let config = Config::from_args(["--strength-min-length", "14"].map(String::from)).unwrap();
+    let app = App::new(&config);
+    assert_eq!(strength_for_mode(&app, "Abcdefgh12!x"), Strength::Moderate);
+    assert!(Config::from_args(["--strength-min-length", "0"].map(String::from)).is_err());
+}
+
+#[test]
+fn rerolling_symbols_keeps_every_other_position() {
+    let mut app = App::new(&Config::default());
+    app.focus = 2;
+    for _ in 0..20 {
+        let before: Vec<char> = app.password.chars().collect();
+        app.reroll_focused();
+        let after: Vec<char> = app.password.chars().collect();
+        assert_eq!(before.len(), after.len());
+        for (old, new) in before.iter().zip(&after) {
+            assert_eq!(CharClass::of(*old), CharClass::of(*new));
+            if CharClass::of(*old) != CharClass::Symbols {
+                assert_eq!(old, new);
+            }
+        }
+        assert_eq!(app.previous_password, before.iter().collect::<String>());
+    }
+}
+
+#[test]
+fn rerolling_goes_through_the_repairs_and_checks() {
+    let config = Config::from_args(
+        [
+            "--letters",
+            "20",
+            "--uppercase",
+            "0",
+            "--symbols",
+            "0",
+            "--numbers",
+            "4",
+            "--distinct",
+            "--no-sequences",
+            "--no-leading-zero",
+            "--match",
+            "^[^q]+$",
+        ]
+        .map(String::from),
+    )
+    .unwrap();
+    let mut app = App::new(&config);
+    app.focus = 0;
+    for _ in 0..50 {
+        let digits: Vec<char> = app.password.chars().filter(char::is_ascii_digit).collect();
+        app.reroll_focused();
+        let chars: Vec<char> = app.password.chars().collect();
+        assert_eq!(chars.len(), 24);
+        let unique: HashSet<char> = chars.iter().copied().collect();
+        assert_eq!(unique.len(), chars.len(), "{}", app.password);
+        assert!(find_sequence(&chars).is_none(), "{}", app.password);
+        assert_ne!(chars[0], '0');
+        assert!(!app.password.contains('q') && !app.unmatched);
+        let mut after: Vec<char> = chars.into_iter().filter(char::is_ascii_digit).collect();
+        let mut before = digits;
+        after.sort_unstable();
+        before.sort_unstable();
+        assert_eq!(before, after);
+    }
+}
+
+#[test]
+fn rerolling_needs_a_plain_character_field() {
+    let mut app = App::new(&Config::default());
+    app.focus = FOCUS_GENERATE;
+    let before = app.password.clone();
+    app.reroll_focused();
+    assert_eq!(app.password, before);
+    assert!(!app.status.is_empty());
+
+    let mut app = App::new(&Config::from_args(["--symbols", "0"].map(String::from)).unwrap());
+    app.focus = 2;
+    let before = app.password.clone();
+    app.reroll_focused();
+    assert_eq!(app.password, before);
+}
+
+#[test]
+fn derived_passwords_reproduce_and_diverge_per_site() {
+    // the full round count takes seconds in a debug build
+    let derive = |master: &str, site: &str, spec: &PasswordSpec| {
+        derive_password_with_rounds(master, site, spec, 1000)
+    };
+    let spec = PasswordSpec {
+        letters: Some(6),
+        uppercase: Some(2),
+        symbols: Some(2),
+        numbers: Some(4),
+        ..PasswordSpec::default()
+    };
+    let first = derive("correct horse", "example.com", &spec);
+    assert_eq!(first.chars().count(), 14);
+    assert_eq!(first, derive("correct horse", " Example.com ", &spec));
+    assert_ne!(first, derive("correct horse", "example.org", &spec));
+    assert_ne!(first, derive("correct horsf", "example.com", &spec));
+    assert_ne!(
+        first,
+        derive_password_with_rounds("correct horse", "example.com", &spec, 1001)
+    );
+
+    let longer = PasswordSpec {
+        numbers: Some(5),
+        ..spec
+    };
+    let derived = derive("correct horse", "example.com", &longer);
+    assert_eq!(derived.chars().filter(char::is_ascii_digit).count(), 5);
+    assert!(!derived.starts_with(&first));
+}
+
+#[test]
+fn derived_passwords_match_known_answers() {
+    // any change here breaks every password users have already derived;
+    // bump DERIVE_VERSION instead
+    let spec = PasswordSpec {
+        letters: Some(6),
+        uppercase: Some(2),
+        symbols: Some(2),
+        numbers: Some(4),
+        ..PasswordSpec::default()
+    };
+    assert_eq!(
+        derive_password_with_rounds("correct horse", "example.com", &spec, 1000),
+        "c(9u1hR+4Ni5fs"
+    );
+    assert_eq!(
+        derive_password("correct horse", "example.com", &spec),
+        "nzjjI9V8%5+0th"
+    );
+    let spec = PasswordSpec {
+        numbers: Some(5),
+        ..spec
+    };
+    assert_eq!(
+        derive_password_with_rounds("correct horse", "example.com", &spec, 1000),
+        "30lKM1(01dwx#ky"
+    );
+}
+
+#[test]
+fn derive_rejects_an_empty_master() {
+    let spec = PasswordSpec::default();
+    assert_eq!(run_derive("example.com", &spec, &mut "\n".as_bytes()), 2);
+}
+
+#[test]
+fn pool_sufficiency_is_count_against_size() {
+    assert!(pool_sufficient(0, 0));
+    assert!(pool_sufficient(3, 3));
+    assert!(!pool_sufficient(4, 3));
+    assert!(!pool_sufficient(1, 0));
+}
+
+#[test]
+fn tiny_pools_warn_about_repeats() {
+    let args = ["--symbol-set", "!", "--symbols", "5"].map(String::from);
+    let app = App::new(&Config::from_args(args).unwrap());
+    assert_eq!(app.undersized_pools(&app.pools()), ["symbols"]);
+    assert!(app.status.contains("symbols"), "{}", app.status);
+    assert!(app.password.matches('!').count() == 5);
+
+    let args = ["--symbol-set", "!#", "--symbols", "2"].map(String::from);
+    let app = App::new(&Config::from_args(args).unwrap());
+    assert!(app.undersized_pools(&app.pools()).is_empty());
+    assert!(app.status.is_empty(), "{}", app.status);
+}
+
+#[test]
+fn class_counts_follow_the_generated_string() {
+    let symbols = chars_of(SYMBOLS);
+    let cases = [
+        ("abcdefGH!#1234", "6 lower, 2 upper, 2 sym, 4 digit"),
+        ("", "0 lower, 0 upper, 0 sym, 0 digit"),
+        ("A1@ z", "1 lower, 1 upper, 0 sym, 1 digit, 2 other"),
+        ("%%%%", "0 lower, 0 upper, 4 sym, 0 digit"),
+    ];
+    for (password, expected) in cases {
+        assert_eq!(class_counts(password, &symbols).to_string(), expected);
+    }
+    assert_eq!(class_counts("A1@", &['@']).symbols, 1);
+}
+
+#[test]
+fn pausing_the_clipboard_clear_keeps_the_remaining_time() {
+    let mut app = App::new(&Config::default());
+    app.clipboard_clear_at = Some(Instant::now() + Duration::from_secs(10));
+    app.pause_clipboard_clear();
+    assert!(app.clipboard_clear_at.is_none());
+    let remaining = app.clipboard_clear_paused.unwrap();
+    assert!(remaining <= Duration::from_secs(10));
+    assert!(remaining > Duration::from_secs(9));
+    assert_eq!(app.status_line(), "Clipboard clear paused (10s left)");
+
+    // a paused countdown never fires
+    app.clear_clipboard_if_due();
+    assert_eq!(app.clipboard_clear_paused, Some(remaining));
+
+    std::thread::sleep(Duration::from_millis(20));
+    let before_resume = Instant::now();
+    app.toggle_clipboard_clear_pause();
+    let deadline = app.clipboard_clear_at.unwrap();
+    assert!(deadline >= before_resume + remaining);
+    assert!(app.clipboard_clear_paused.is_none());
+    assert!(app.status_line().starts_with("Clipboard clears in "));
+}
+
+#[test]
+fn pause_without_a_countdown_reports_it() {
+    let mut app = App::new(&Config::default());
+    app.status.clear();
+    app.toggle_clipboard_clear_pause();
+    assert!(app.clipboard_clear_paused.is_none());
+    assert_eq!(app.status, "No clipboard clear is pending.");
+}
+
+#[test]
+fn affixes_wrap_the_random_core() {
+    let args = ["--prefix", "Corp-", "--suffix", "!x"].map(String::from);
+    let mut app = App::new(&Config::from_args(args).unwrap());
+    let plain = App::new(&Config::default());
+    for _ in 0..10 {
+        app.generate_password();
+        assert!(app.password.starts_with("Corp-"), "{}", app.password);
+        assert!(app.password.ends_with("!x"), "{}", app.password);
+        let (prefix, core, suffix) = app.split_affixes(&app.password);
+        assert_eq!((prefix, suffix), ("Corp-", "!x"));
+        assert_eq!(core.chars().count(), 14);
+        assert_eq!(app.strength, strength_for_mode(&app, core));
+    }
+    // fixed text adds no entropy
+    assert_eq!(estimate_entropy(&app), estimate_entropy(&plain));
+    assert_eq!(total_length(&app), total_length(&plain));
+
+    let spans = app.password_spans(&palette(ThemeKind::Dark));
+    assert_eq!(spans.first().unwrap().content, "Corp-");
+    assert_eq!(spans.last().unwrap().content, "!x");
+    assert_ne!(spans[0].style, spans[1].style);
+}
+
+#[test]
+fn affixes_do_not_raise_strength() {
+    let args = [
+        "--prefix",
+        "Aa1!Aa1!Aa1!",
+        "--letters",
+        "3",
+        "--uppercase",
+        "0",
+    ];
+    let args = args.into_iter().chain(["--symbols", "0", "--numbers", "0"]);
+    let app = App::new(&Config::from_args(args.map(String::from)).unwrap());
+    assert_eq!(app.strength, Strength::DoNotUse);
+    assert_eq!(app.affixed_strength(&app.password), Strength::DoNotUse);
+    assert!(Config::from_args(["--prefix", "a b"].map(String::from)).is_err());
+}
+
+#[test]
+fn content_checks_see_the_affixes() {
+    let args = ["--prefix", "Corp-", "--match", "^Corp-[^q]+$"].map(String::from);
+    let mut app = App::new(&Config::from_args(args).unwrap());
+    app.denylist = vec!["p-x".to_string()];
+    for _ in 0..20 {
+        app.generate_password();
+        assert!(!app.unmatched && !app.denied, "{}", app.password);
+        assert!(!app.password.to_lowercase().contains("p-x"));
+        let core = app.split_affixes(&app.password).1;
+        assert_eq!(app.strength, strength_for_mode(&app, core));
+    }
+    // a fixed affix that breaks a check fails every candidate
+    app.denylist = vec!["corp".to_string()];
+    app.generate_password();
+    assert!(app.denied);
+
+    let parsed = Config::from_args(["--prefix", "Caf\u{e9}-"].map(String::from));
+    assert!(parsed.is_err_and(|err| err.contains("--allow-unicode")));
+    for args in [
+        ["--allow-unicode", "--suffix", "-\u{e9}t\u{e9}"],
+        ["--suffix", "-\u{e9}t\u{e9}", "--allow-unicode"],
+    ] {
+        let app = App::new(&Config::from_args(args.map(String::from)).unwrap());
+        assert!(app.password.ends_with("-\u{e9}t\u{e9}"));
+        assert!(!app.unsafe_chars);
+    }
+}
+
+proptest! {
+    #[test]
+    fn generated_password_length_matches_sum(
+        letters in 0i32..20,
+        uppercase in 0i32..20,
+        symbols in 0i32..20,
+        numbers in 0i32..20,
+        seed in any::<u64>(),
+    ) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let password = generate_password(letters, uppercase, symbols, numbers, &mut rng);
+        let expected_len = (letters + uppercase + symbols + numbers) as usize;
+        prop_assert_eq!(password.len(), expected_len);
+    }
+
+    #[test]
+    fn generated_password_only_has_allowed_chars(
+        letters in 0i32..20,
+        uppercase in 0i32..20,
+        symbols in 0i32..20,
+        numbers in 0i32..20,
+        seed in any::<u64>(),
+    ) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let password = generate_password(letters, uppercase, symbols, numbers, &mut rng);
+        for ch in password.chars() {
+            let is_lower = ch.is_ascii_lowercase();
+            let is_upper = ch.is_ascii_uppercase();
+            let is_digit = ch.is_ascii_digit();
+            let is_symbol = SYMBOLS.contains(&(ch as u8));
+            prop_assert!(is_lower || is_upper || is_digit || is_symbol);
+        }
+    }
+
+    #[test]
+    fn remove_sequences_leaves_no_runs(
+        letters in 0i32..20,
+        uppercase in 0i32..20,
+        symbols in 0i32..20,
+        numbers in 0i32..20,
+        seed in any::<u64>(),
+    ) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let password = generate_password(letters, uppercase, symbols, numbers, &mut rng);
+        let mut password = chars(&password);
+        let pools = Pools::new(&chars_of(SYMBOLS), &[]);
+        remove_sequences(&mut password, &[], &pools, false, &mut rng);
+        prop_assert_eq!(find_sequence(&password), None);
+        prop_assert_eq!(password.len(), (letters + uppercase + symbols + numbers) as usize);
+    }
+
+    #[test]
+    fn split_letters_never_exceeds_total(total in 0i32..500, pct in 0i32..=100) {
+        let (lower, upper) = split_letters(total, pct);
+        prop_assert_eq!(lower + upper, total);
+        prop_assert!(lower >= 0 && upper >= 0);
+    }
+}
@@ -0,0 +1,281 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn genpass(args: &[&str]) -> std::process::Output {
+    // an empty config dir, so saved settings like a prefix can't leak in
+    let config = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("no-config");
+    Command::new(env!("CARGO_BIN_EXE_GenPass"))
+        .env("XDG_CONFIG_HOME", &config)
+        .env("APPDATA", &config)
+        .args(args)
+        .output()
+        .expect("failed to run genpass")
+}
+
+#[test]
+fn satisfiable_min_strength_exits_zero() {
+    let output = genpass(&["--print", "--min-strength", "strong"]);
+    assert_eq!(output.status.code(), Some(0));
+    let password = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(password.trim_end().len(), 14);
+}
+
+#[test]
+fn unsatisfiable_min_strength_exits_four() {
+    let output = genpass(&[
+        "--print",
+        "--min-strength",
+        "strong",
+        "--letters",
+        "2",
+        "--uppercase",
+        "0",
+        "--symbols",
+        "0",
+        "--numbers",
+        "0",
+    ]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Strong"));
+}
+
+#[test]
+fn unmet_constraints_exit_four() {
+    let unmatched = genpass(&["--print", "--match", "^never$"]);
+    assert_eq!(unmatched.status.code(), Some(4));
+    let short = genpass(&["--print", "--distinct", "--numbers", "11"]);
+    assert_eq!(short.status.code(), Some(4));
+}
+
+#[test]
+fn match_accepts_lookahead() {
+    let output = genpass(&["--print", "--match", r"^(?=.*[A-Z])(?=.*\d).{12,}$"]);
+    assert!(output.status.success());
+    let password = String::from_utf8(output.stdout).unwrap();
+    let password = password.trim_end();
+    assert!(password.chars().count() >= 12);
+    assert!(password.chars().any(|ch| ch.is_ascii_uppercase()));
+    assert!(password.chars().any(|ch| ch.is_ascii_digit()));
+}
+
+#[test]
+fn match_sees_the_prefix() {
+    let output = genpass(&["--print", "--prefix", "Corp-", "--match", "^Corp-"]);
+    assert_eq!(output.status.code(), Some(0));
+    let password = String::from_utf8(output.stdout).unwrap();
+    assert!(password.starts_with("Corp-"));
+
+    let output = genpass(&["--print", "--prefix", "Caf\u{e9}-"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn invalid_arguments_exit_two() {
+    let output = genpass(&["--print", "--letters", "many"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+fn genpass_check(input: &str) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_GenPass"))
+        .arg("--check")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run genpass");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn check_scores_weak_password() {
+    let output = genpass_check("hunter2\n");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "Strength: Do not use!!!!");
+    assert!(lines[1].starts_with("Entropy: "));
+    assert!(lines.contains(&"Lowercase: yes"));
+    assert!(lines.contains(&"Symbol: no"));
+}
+
+#[test]
+fn check_scores_strong_password() {
+    let output = genpass_check("Tr0ub4dor&3xyz\n");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Strength: Strong\n"));
+    assert!(stdout.contains("Length (10+): yes"));
+}
+
+#[test]
+fn check_rejects_empty_stdin() {
+    let output = genpass_check("");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn spec_env_sets_defaults_below_flags() {
+    let output = Command::new(env!("CARGO_BIN_EXE_GenPass"))
+        .args(["--print", "--numbers", "1"])
+        .env("GENPASS_SPEC", "l=3,u=0,s=0,n=9")
+        .output()
+        .expect("failed to run genpass");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim_end().len(),
+        4
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_GenPass"))
+        .arg("--print")
+        .env("GENPASS_SPEC", "bogus")
+        .output()
+        .expect("failed to run genpass");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim_end().len(),
+        14
+    );
+}
+
+#[test]
+fn out_writes_count_passwords_and_refuses_overwrite() {
+    let path = std::env::temp_dir().join(format!("genpass-cli-out-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let path_arg = path.to_str().unwrap();
+
+    let output = genpass(&["--count", "10", "--out", path_arg]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 10);
+
+    let output = genpass(&["--out", path_arg]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+
+    let output = genpass(&["--out", path_arg, "--force"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn dry_run_prints_resolved_spec() {
+    let output = Command::new(env!("CARGO_BIN_EXE_GenPass"))
+        .args(["--dry-run", "--numbers", "7", "--no-sequences"])
+        .env("GENPASS_SPEC", "l=11,n=2")
+        .output()
+        .expect("failed to run genpass");
+    assert_eq!(output.status.code(), Some(0));
+    let spec = String::from_utf8(output.stdout).unwrap();
+    assert!(spec.contains("letters: 11\n"));
+    assert!(spec.contains("numbers: 7\n"));
+    assert!(spec.contains("no sequences: on\n"));
+    assert!(spec.contains("mode: Random\n"));
+}
+
+#[test]
+fn simple_mode_generates_on_each_enter() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_GenPass"))
+        .arg("--simple")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run genpass");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"\n\nx\nq\n\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|line| line.chars().count() == 14));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown command \"x\""));
+}
+
+#[test]
+fn export_prints_one_assignment_per_name() {
+    let output = genpass(&["--export", "DB_PASSWORD", "--export", "API_KEY"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("export DB_PASSWORD='"));
+    assert!(lines[1].starts_with("export API_KEY='"));
+    assert!(lines.iter().all(|line| line.ends_with('\'')));
+}
+
+#[cfg(unix)]
+#[test]
+fn copy_quiet_writes_clipboard_and_prints_nothing() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("genpass-cli-copy-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let clipboard = dir.join("clipboard");
+    let script = format!("#!/bin/sh\nexec /bin/cat > '{}'\n", clipboard.display());
+    for tool in ["wl-copy", "pbcopy"] {
+        let path = dir.join(tool);
+        std::fs::write(&path, &script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_GenPass"))
+        .args(["--copy", "--quiet"])
+        .env("PATH", &dir)
+        .output()
+        .expect("failed to run genpass");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert_eq!(std::fs::read_to_string(&clipboard).unwrap().len(), 14);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn copy_without_clipboard_backend_exits_three() {
+    let dir = std::env::temp_dir().join(format!("genpass-cli-nocopy-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_GenPass"))
+        .args(["--print", "--copy"])
+        .env("PATH", &dir)
+        .output()
+        .expect("failed to run genpass");
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No clipboard tool found"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn jsonl_prints_one_json_object_per_password() {
+    let output = genpass(&["--jsonl", "--count", "5", "--symbol-set", "\"\\"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let line =
+        regex::Regex::new(r#"^\{"index":(\d+),"password":"((?:[^"\\]|\\["\\])*)"\}$"#).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 5);
+    for (index, text) in lines.iter().enumerate() {
+        let captures = line
+            .captures(text)
+            .unwrap_or_else(|| panic!("not JSON: {text}"));
+        assert_eq!(captures[1].parse::<usize>().unwrap(), index);
+        let password = captures[2].replace("\\\"", "\"").replace("\\\\", "\\");
+        assert_eq!(password.chars().count(), 14);
+    }
+}